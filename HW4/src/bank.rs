@@ -0,0 +1,597 @@
+//! bank — deposit accounts and loans in exact copper pieces
+//!
+//! A `Bank` keeps deposit accounts that earn compound interest every game
+//! day, and issues loans that are repaid in fixed installments. Installments
+//! not paid within the grace period cost a late fee; a loan with too many
+//! overdue installments defaults.
+//!
+//! Notes:
+//! - Rates are parts per million (ppm): per day for deposits, per period for
+//!   loans. 1000 ppm is 0.1%.
+//! - Every division says how it rounds (`Rounding`). Nothing is stored in
+//!   fractions of a cp.
+//! - Loan schedules are built with the same integer interest the bank will
+//!   charge, so the last installment leaves exactly 0 cp owing.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::GameDay;
+use crate::money::{Money, MoneyError};
+
+/// Rates are given in millionths.
+const PPM: u128 = 1_000_000;
+
+/// Rounding
+///
+/// Purpose: How a fractional cp is settled. `BankTerms` uses one rounding
+/// for deposit and loan interest alike, so whichever side it favours on
+/// deposits it favours the other way on loans.
+/// Type: `enum Rounding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero: less interest, so depositors earn less and borrowers
+    /// owe less.
+    Down,
+    /// Away from zero: more interest, so depositors earn more and
+    /// borrowers owe more.
+    Up,
+    /// To the nearest cp, ties to even (banker's rounding).
+    HalfEven,
+}
+
+impl Rounding {
+    /// divide
+    ///
+    /// Purpose: `num / den`, rounded this way. `den` must not be zero.
+    /// Type: `fn divide(self, num: u128, den: u128) -> u128`
+    pub fn divide(self, num: u128, den: u128) -> u128 {
+        let (q, r) = (num / den, num % den);
+        match self {
+            Rounding::Down => q,
+            Rounding::Up => q + u128::from(r != 0),
+            Rounding::HalfEven => match (2 * r).cmp(&den) {
+                std::cmp::Ordering::Less => q,
+                std::cmp::Ordering::Greater => q + 1,
+                std::cmp::Ordering::Equal => q + q % 2,
+            },
+        }
+    }
+
+    /// interest
+    ///
+    /// Purpose: Interest on `balance` at `rate_ppm`, rounded this way.
+    /// Type: `fn interest(self, balance: Money, rate_ppm: u32) -> Result<Money, MoneyError>`
+    pub fn interest(self, balance: Money, rate_ppm: u32) -> Result<Money, MoneyError> {
+        let cp = self.divide(u128::from(balance.cp()) * u128::from(rate_ppm), PPM);
+        u64::try_from(cp).map(Money::from_cp).map_err(|_| MoneyError::Overflow)
+    }
+}
+
+/// BankTerms
+///
+/// Purpose: The levers designers can pull on a bank.
+/// Type: `struct BankTerms`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankTerms {
+    /// Daily interest paid on deposits.
+    pub deposit_rate_ppm: u32,
+    /// How deposit and loan interest are rounded (see `Rounding`).
+    pub rounding: Rounding,
+    /// Charged once for each installment that is overdue.
+    pub late_fee: Money,
+    /// Days after the due date before an installment is overdue.
+    pub grace_days: GameDay,
+    /// A loan defaults when this many installments are overdue at once.
+    pub default_after: usize,
+}
+
+impl Default for BankTerms {
+    fn default() -> Self {
+        BankTerms {
+            deposit_rate_ppm: 100,
+            rounding: Rounding::Down,
+            late_fee: Money::from_cp(50),
+            grace_days: 5,
+            default_after: 3,
+        }
+    }
+}
+
+/// DepositId
+///
+/// Purpose: Handle for a deposit account at a `Bank`.
+/// Type: `struct DepositId(usize)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DepositId(usize);
+
+/// LoanId
+///
+/// Purpose: Handle for a loan issued by a `Bank`.
+/// Type: `struct LoanId(usize)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LoanId(usize);
+
+/// Deposit
+///
+/// Purpose: A deposit account and the interest it has earned.
+/// Type: `struct Deposit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    pub owner: String,
+    pub balance: Money,
+    pub interest_earned: Money,
+}
+
+/// Installment
+///
+/// Purpose: One scheduled loan payment, split into interest and principal.
+/// Type: `struct Installment`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Installment {
+    pub due_day: GameDay,
+    pub payment: Money,
+    pub interest: Money,
+    pub principal: Money,
+    /// Principal still owed after this payment.
+    pub balance_after: Money,
+}
+
+/// LoanStatus
+///
+/// Purpose: Where a loan stands.
+/// Type: `enum LoanStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoanStatus {
+    Current,
+    Late { overdue: usize },
+    Defaulted { on: GameDay },
+    PaidOff,
+}
+
+impl fmt::Display for LoanStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoanStatus::Current => write!(f, "current"),
+            LoanStatus::Late { overdue: 1 } => write!(f, "late, 1 installment overdue"),
+            LoanStatus::Late { overdue } => write!(f, "late, {overdue} installments overdue"),
+            LoanStatus::Defaulted { on } => write!(f, "defaulted on day {on}"),
+            LoanStatus::PaidOff => write!(f, "paid off"),
+        }
+    }
+}
+
+/// Loan
+///
+/// Purpose: A loan, its schedule and how much of it has been paid.
+/// Type: `struct Loan`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loan {
+    pub borrower: String,
+    pub principal: Money,
+    pub schedule: Vec<Installment>,
+    /// Index of the first installment not yet fully paid.
+    next: usize,
+    /// Paid so far toward installment `next`.
+    paid_toward_next: Money,
+    /// Late fees charged and not yet paid.
+    pub fees_due: Money,
+    /// Installments before this index have been charged a late fee.
+    fees_charged: usize,
+    defaulted_on: Option<GameDay>,
+}
+
+impl Loan {
+    /// overdue
+    ///
+    /// Purpose: How many unpaid installments are past due and grace on `day`.
+    /// Type: `fn overdue(&self, day: GameDay, grace_days: GameDay) -> usize`
+    pub fn overdue(&self, day: GameDay, grace_days: GameDay) -> usize {
+        self.schedule[self.next..].iter().take_while(|i| i.due_day.saturating_add(grace_days) < day).count()
+    }
+
+    /// status
+    ///
+    /// Purpose: Where the loan stands on `day`.
+    /// Type: `fn status(&self, day: GameDay, grace_days: GameDay) -> LoanStatus`
+    pub fn status(&self, day: GameDay, grace_days: GameDay) -> LoanStatus {
+        if let Some(on) = self.defaulted_on {
+            return LoanStatus::Defaulted { on };
+        }
+        if self.next == self.schedule.len() && self.fees_due == Money::ZERO {
+            return LoanStatus::PaidOff;
+        }
+        match self.overdue(day, grace_days) {
+            0 => LoanStatus::Current,
+            overdue => LoanStatus::Late { overdue },
+        }
+    }
+
+    /// outstanding
+    ///
+    /// Purpose: Everything still owed: unpaid installments plus fees.
+    /// Type: `fn outstanding(&self) -> Result<Money, MoneyError>`
+    pub fn outstanding(&self) -> Result<Money, MoneyError> {
+        let scheduled = self.schedule[self.next..].iter().map(|i| i.payment).sum::<Result<Money, MoneyError>>()?;
+        (scheduled - self.paid_toward_next)? + self.fees_due
+    }
+}
+
+/// BankError
+///
+/// Purpose: Why a bank operation was refused.
+/// Type: `enum BankError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankError {
+    UnknownDeposit(DepositId),
+    UnknownLoan(LoanId),
+    ZeroAmount,
+    InsufficientFunds { requested: Money, available: Money },
+    /// A loan needs a principal, at least one period and periods of at least a day.
+    InvalidTerms,
+    LoanPaidOff(LoanId),
+    Money(MoneyError),
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::UnknownDeposit(id) => write!(f, "no deposit account {}", id.0),
+            BankError::UnknownLoan(id) => write!(f, "no loan {}", id.0),
+            BankError::ZeroAmount => write!(f, "amount must not be zero"),
+            BankError::InsufficientFunds { requested, available } => {
+                write!(f, "insufficient funds: requested {requested}, available {available}")
+            }
+            BankError::InvalidTerms => write!(f, "loan needs a principal and at least one period of a day or more"),
+            BankError::LoanPaidOff(id) => write!(f, "loan {} is already paid off", id.0),
+            BankError::Money(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for BankError {}
+
+impl From<MoneyError> for BankError {
+    fn from(e: MoneyError) -> Self {
+        BankError::Money(e)
+    }
+}
+
+/// amortize
+///
+/// Purpose: Build a schedule of equal installments that repays `principal`
+/// over `periods` periods of `period_days`, with interest of `rate_ppm` per
+/// period on the remaining principal.
+/// Returns: the schedule. Every installment is the same, except the last,
+/// which may be smaller so the balance ends at exactly 0 cp.
+/// Type: `fn amortize(principal: Money, rate_ppm: u32, periods: u32, period_days: GameDay, start_day: GameDay, rounding: Rounding) -> Result<Vec<Installment>, BankError>`
+pub fn amortize(
+    principal: Money,
+    rate_ppm: u32,
+    periods: u32,
+    period_days: GameDay,
+    start_day: GameDay,
+    rounding: Rounding,
+) -> Result<Vec<Installment>, BankError> {
+    if principal == Money::ZERO || periods == 0 || period_days == 0 {
+        return Err(BankError::InvalidTerms);
+    }
+    // The balance never rises above the principal, so this always pays it off.
+    let mut high = principal.cp().div_ceil(u64::from(periods)) + rounding.interest(principal, rate_ppm)?.cp();
+    let mut low = 1;
+    // Smallest installment that clears the loan in time, by binary search.
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if schedule(principal, rate_ppm, periods, period_days, start_day, rounding, Money::from_cp(mid))?.is_some() {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    schedule(principal, rate_ppm, periods, period_days, start_day, rounding, Money::from_cp(low))?.ok_or(BankError::InvalidTerms)
+}
+
+/// schedule
+///
+/// Purpose: The schedule for a fixed `payment`, or `None` if it does not
+/// clear the loan within `periods`.
+fn schedule(
+    principal: Money,
+    rate_ppm: u32,
+    periods: u32,
+    period_days: GameDay,
+    start_day: GameDay,
+    rounding: Rounding,
+    payment: Money,
+) -> Result<Option<Vec<Installment>>, BankError> {
+    let mut balance = principal;
+    let mut installments = Vec::new();
+    for n in 1..=periods {
+        let interest = rounding.interest(balance, rate_ppm)?;
+        let owed = (balance + interest)?;
+        let payment = payment.min(owed);
+        let principal = (payment - interest).unwrap_or(Money::ZERO);
+        balance = (owed - payment)?;
+        let due_day = start_day.checked_add(period_days.checked_mul(n).ok_or(BankError::InvalidTerms)?).ok_or(BankError::InvalidTerms)?;
+        installments.push(Installment { due_day, payment, interest, principal, balance_after: balance });
+        if balance == Money::ZERO {
+            return Ok(Some(installments));
+        }
+    }
+    Ok(None)
+}
+
+/// Bank
+///
+/// Purpose: Deposit accounts and loans, advanced one game day at a time.
+/// Type: `struct Bank`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bank {
+    pub terms: BankTerms,
+    day: GameDay,
+    deposits: Vec<Deposit>,
+    loans: Vec<Loan>,
+}
+
+impl Bank {
+    /// new
+    ///
+    /// Purpose: A bank opening on `day` with `terms`.
+    /// Type: `fn new(terms: BankTerms, day: GameDay) -> Bank`
+    pub fn new(terms: BankTerms, day: GameDay) -> Bank {
+        Bank { terms, day, deposits: Vec::new(), loans: Vec::new() }
+    }
+
+    /// day
+    ///
+    /// Purpose: The bank's current game day.
+    /// Type: `fn day(&self) -> GameDay`
+    pub fn day(&self) -> GameDay {
+        self.day
+    }
+
+    /// open_deposit
+    ///
+    /// Purpose: Open an empty deposit account for `owner`.
+    /// Type: `fn open_deposit(&mut self, owner: &str) -> DepositId`
+    pub fn open_deposit(&mut self, owner: &str) -> DepositId {
+        self.deposits.push(Deposit { owner: owner.to_string(), balance: Money::ZERO, interest_earned: Money::ZERO });
+        DepositId(self.deposits.len() - 1)
+    }
+
+    /// deposit
+    ///
+    /// Purpose: Pay `amount` into an account.
+    /// Type: `fn deposit(&mut self, id: DepositId, amount: Money) -> Result<Money, BankError>`
+    pub fn deposit(&mut self, id: DepositId, amount: Money) -> Result<Money, BankError> {
+        if amount == Money::ZERO {
+            return Err(BankError::ZeroAmount);
+        }
+        let account = self.deposits.get_mut(id.0).ok_or(BankError::UnknownDeposit(id))?;
+        account.balance = (account.balance + amount)?;
+        Ok(account.balance)
+    }
+
+    /// withdraw
+    ///
+    /// Purpose: Take `amount` out of an account.
+    /// Type: `fn withdraw(&mut self, id: DepositId, amount: Money) -> Result<Money, BankError>`
+    pub fn withdraw(&mut self, id: DepositId, amount: Money) -> Result<Money, BankError> {
+        if amount == Money::ZERO {
+            return Err(BankError::ZeroAmount);
+        }
+        let account = self.deposits.get_mut(id.0).ok_or(BankError::UnknownDeposit(id))?;
+        account.balance = (account.balance - amount)
+            .map_err(|_| BankError::InsufficientFunds { requested: amount, available: account.balance })?;
+        Ok(account.balance)
+    }
+
+    /// deposit_account
+    ///
+    /// Purpose: Look up a deposit account.
+    /// Type: `fn deposit_account(&self, id: DepositId) -> Option<&Deposit>`
+    pub fn deposit_account(&self, id: DepositId) -> Option<&Deposit> {
+        self.deposits.get(id.0)
+    }
+
+    /// issue_loan
+    ///
+    /// Purpose: Lend `principal` to `borrower` today, repaid over `periods`
+    /// installments every `period_days` at `rate_ppm` per period.
+    /// Type: `fn issue_loan(&mut self, borrower: &str, principal: Money, rate_ppm: u32, periods: u32, period_days: GameDay) -> Result<LoanId, BankError>`
+    pub fn issue_loan(
+        &mut self,
+        borrower: &str,
+        principal: Money,
+        rate_ppm: u32,
+        periods: u32,
+        period_days: GameDay,
+    ) -> Result<LoanId, BankError> {
+        let schedule = amortize(principal, rate_ppm, periods, period_days, self.day, self.terms.rounding)?;
+        self.loans.push(Loan {
+            borrower: borrower.to_string(),
+            principal,
+            schedule,
+            next: 0,
+            paid_toward_next: Money::ZERO,
+            fees_due: Money::ZERO,
+            fees_charged: 0,
+            defaulted_on: None,
+        });
+        Ok(LoanId(self.loans.len() - 1))
+    }
+
+    /// loan
+    ///
+    /// Purpose: Look up a loan.
+    /// Type: `fn loan(&self, id: LoanId) -> Option<&Loan>`
+    pub fn loan(&self, id: LoanId) -> Option<&Loan> {
+        self.loans.get(id.0)
+    }
+
+    /// loan_status
+    ///
+    /// Purpose: Where a loan stands today.
+    /// Type: `fn loan_status(&self, id: LoanId) -> Result<LoanStatus, BankError>`
+    pub fn loan_status(&self, id: LoanId) -> Result<LoanStatus, BankError> {
+        let loan = self.loan(id).ok_or(BankError::UnknownLoan(id))?;
+        Ok(loan.status(self.day, self.terms.grace_days))
+    }
+
+    /// pay_loan
+    ///
+    /// Purpose: Pay `amount` toward a loan: late fees first, then
+    /// installments in order. Defaulted loans still accept payments.
+    /// Returns: whatever was not needed, once the loan is paid off.
+    /// Type: `fn pay_loan(&mut self, id: LoanId, amount: Money) -> Result<Money, BankError>`
+    pub fn pay_loan(&mut self, id: LoanId, amount: Money) -> Result<Money, BankError> {
+        if amount == Money::ZERO {
+            return Err(BankError::ZeroAmount);
+        }
+        let loan = self.loans.get_mut(id.0).ok_or(BankError::UnknownLoan(id))?;
+        if loan.next == loan.schedule.len() && loan.fees_due == Money::ZERO {
+            return Err(BankError::LoanPaidOff(id));
+        }
+        let to_fees = amount.min(loan.fees_due);
+        loan.fees_due = (loan.fees_due - to_fees)?;
+        let mut left = (amount - to_fees)?;
+        while let Some(installment) = loan.schedule.get(loan.next) {
+            let needed = (installment.payment - loan.paid_toward_next)?;
+            if left < needed {
+                loan.paid_toward_next = (loan.paid_toward_next + left)?;
+                return Ok(Money::ZERO);
+            }
+            left = (left - needed)?;
+            loan.paid_toward_next = Money::ZERO;
+            loan.next += 1;
+        }
+        Ok(left)
+    }
+
+    /// advance_to
+    ///
+    /// Purpose: Run the bank day by day up to `day`: compound deposit
+    /// interest, charge late fees and mark defaults.
+    /// Type: `fn advance_to(&mut self, day: GameDay) -> Result<(), BankError>`
+    pub fn advance_to(&mut self, day: GameDay) -> Result<(), BankError> {
+        while self.day < day {
+            self.day += 1;
+            for account in &mut self.deposits {
+                let interest = self.terms.rounding.interest(account.balance, self.terms.deposit_rate_ppm)?;
+                account.balance = (account.balance + interest)?;
+                account.interest_earned = (account.interest_earned + interest)?;
+            }
+            for loan in &mut self.loans {
+                if loan.defaulted_on.is_some() {
+                    continue;
+                }
+                let overdue = loan.overdue(self.day, self.terms.grace_days);
+                let newly_late = (loan.next + overdue).saturating_sub(loan.fees_charged.max(loan.next));
+                if newly_late > 0 {
+                    let newly_late = u32::try_from(newly_late).map_err(|_| MoneyError::Overflow)?;
+                    let fees = (self.terms.late_fee * newly_late)?;
+                    loan.fees_due = (loan.fees_due + fees)?;
+                    loan.fees_charged = loan.next + overdue;
+                }
+                if overdue >= self.terms.default_after.max(1) {
+                    loan.defaulted_on = Some(self.day);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cp(n: u64) -> Money {
+        Money::from_cp(n)
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        assert_eq!(Rounding::Down.divide(7, 2), 3);
+        assert_eq!(Rounding::Up.divide(7, 2), 4);
+        assert_eq!(Rounding::HalfEven.divide(5, 2), 2);
+        assert_eq!(Rounding::HalfEven.divide(7, 2), 4);
+        assert_eq!(Rounding::HalfEven.divide(8, 3), 3);
+        assert_eq!(Rounding::Up.divide(6, 2), 3);
+        assert_eq!(Rounding::Down.interest(cp(999), 1000), Ok(cp(0)));
+        assert_eq!(Rounding::Up.interest(cp(999), 1000), Ok(cp(1)));
+    }
+
+    #[test]
+    fn test_deposits_compound_daily() {
+        let terms = BankTerms { deposit_rate_ppm: 1000, ..BankTerms::default() };
+        let mut bank = Bank::new(terms, 0);
+        let id = bank.open_deposit("Aria");
+        bank.deposit(id, cp(1000)).unwrap();
+        bank.advance_to(10).unwrap();
+        // 0.1% a day, rounded down: exactly 1 cp a day until the balance reaches 2000.
+        assert_eq!(bank.deposit_account(id).unwrap().balance, cp(1010));
+        assert_eq!(bank.deposit_account(id).unwrap().interest_earned, cp(10));
+
+        let mut generous = Bank::new(BankTerms { rounding: Rounding::Up, ..terms }, 0);
+        let id2 = generous.open_deposit("Bram");
+        generous.deposit(id2, cp(1000)).unwrap();
+        generous.advance_to(10).unwrap();
+        // Rounding up: 1 cp on day 1, then 2 cp a day.
+        assert_eq!(generous.deposit_account(id2).unwrap().balance, cp(1019));
+
+        assert_eq!(bank.withdraw(id, cp(2000)), Err(BankError::InsufficientFunds { requested: cp(2000), available: cp(1010) }));
+        assert_eq!(bank.withdraw(id, cp(10)), Ok(cp(1000)));
+        assert_eq!(bank.deposit(DepositId(9), cp(1)), Err(BankError::UnknownDeposit(DepositId(9))));
+    }
+
+    #[test]
+    fn test_amortization_schedule() {
+        // 1000 cp at 10% a period over 3 periods: 402, 402, 401.
+        let schedule = amortize(cp(1000), 100_000, 3, 30, 0, Rounding::Down).unwrap();
+        let payments: Vec<u64> = schedule.iter().map(|i| i.payment.cp()).collect();
+        let interest: Vec<u64> = schedule.iter().map(|i| i.interest.cp()).collect();
+        assert_eq!(payments, [402, 402, 401]);
+        assert_eq!(interest, [100, 69, 36]);
+        assert_eq!(schedule.iter().map(|i| i.due_day).collect::<Vec<_>>(), [30, 60, 90]);
+        assert_eq!(schedule.last().unwrap().balance_after, Money::ZERO);
+        let principal: u64 = schedule.iter().map(|i| i.principal.cp()).sum();
+        assert_eq!(principal, 1000);
+
+        let interest_free = amortize(cp(100), 0, 3, 7, 0, Rounding::Down).unwrap();
+        assert_eq!(interest_free.iter().map(|i| i.payment.cp()).collect::<Vec<_>>(), [34, 34, 32]);
+
+        assert_eq!(amortize(cp(100), 0, 0, 7, 0, Rounding::Down), Err(BankError::InvalidTerms));
+        assert_eq!(amortize(Money::ZERO, 0, 3, 7, 0, Rounding::Down), Err(BankError::InvalidTerms));
+    }
+
+    #[test]
+    fn test_loan_payments_late_fees_and_default() {
+        let terms = BankTerms { late_fee: cp(25), grace_days: 5, default_after: 2, ..BankTerms::default() };
+        let mut bank = Bank::new(terms, 0);
+        let loan = bank.issue_loan("Cato", cp(1000), 100_000, 3, 30).unwrap();
+
+        bank.advance_to(35).unwrap();
+        assert_eq!(bank.loan_status(loan), Ok(LoanStatus::Current));
+        bank.advance_to(36).unwrap();
+        assert_eq!(bank.loan_status(loan), Ok(LoanStatus::Late { overdue: 1 }));
+        assert_eq!(LoanStatus::Late { overdue: 1 }.to_string(), "late, 1 installment overdue");
+        assert_eq!(bank.loan(loan).unwrap().fees_due, cp(25));
+        bank.advance_to(50).unwrap();
+        assert_eq!(bank.loan(loan).unwrap().fees_due, cp(25), "a late fee is charged once");
+
+        // Fee first, then the first installment, then part of the second.
+        assert_eq!(bank.pay_loan(loan, cp(500)), Ok(Money::ZERO));
+        assert_eq!(bank.loan(loan).unwrap().outstanding(), Ok(cp(402 + 401 - 73)));
+        assert_eq!(bank.loan_status(loan), Ok(LoanStatus::Current));
+
+        bank.advance_to(96).unwrap();
+        assert_eq!(bank.loan_status(loan), Ok(LoanStatus::Defaulted { on: 96 }));
+        assert_eq!(LoanStatus::Defaulted { on: 96 }.to_string(), "defaulted on day 96");
+        assert_eq!(bank.loan(loan).unwrap().fees_due, cp(50));
+
+        let owed = bank.loan(loan).unwrap().outstanding().unwrap();
+        assert_eq!(bank.pay_loan(loan, (owed + cp(10)).unwrap()), Ok(cp(10)));
+        assert_eq!(bank.pay_loan(loan, cp(1)), Err(BankError::LoanPaidOff(loan)));
+    }
+}
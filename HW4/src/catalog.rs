@@ -0,0 +1,334 @@
+//! catalog — numismatic catalog of mintages, rarity and collector value
+//!
+//! Collectors care about which issue a coin is: its denomination, mint, year
+//! and metal. The `Catalog` records how many of each issue were struck, and
+//! from that rates each coin's rarity and the premium a collector will pay
+//! over face value. It can also tell how complete a wallet's collection of a
+//! set of issues is.
+//!
+//! Notes:
+//! - Issues that are not in the catalog are "unlisted": no rarity, and no
+//!   premium. They may be fakes or errors.
+//! - A coin is never worth less to a collector than its face or its metal.
+//! - Wear eats into the premium: see `Grade::premium_percent`.
+//! - The standard catalog is generated from a fixed seed, so the numbers are
+//!   the same on every run.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::money::{Money, MoneyError};
+use crate::rng::Rng;
+use crate::spec::SpecBook;
+use crate::{Coin, Denomination, Material, Mint};
+
+/// Seed for the standard catalog's mintage figures.
+const STANDARD_SEED: u64 = 0x4E55_4D49_534D;
+
+/// The Mountain mint's Mithril issues, struck in tiny numbers each year.
+const MITHRIL_ISSUES: [(Denomination, u64); 2] = [(Denomination::Gold, 40), (Denomination::Platinum, 12)];
+
+/// Issue
+///
+/// Purpose: One catalog entry: a denomination struck by a mint in a year
+/// in a given metal.
+/// Type: `struct Issue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Issue {
+    pub denom: Denomination,
+    pub mint: Mint,
+    pub year: u16,
+    pub material: Material,
+}
+
+impl fmt::Display for Issue {
+    /// Print an issue as "Mountain Mithril Gold 1020".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?} {:?} {}", self.mint, self.material, self.denom, self.year)
+    }
+}
+
+impl Issue {
+    /// of
+    ///
+    /// Purpose: The issue a coin belongs to.
+    /// Type: `fn of(coin: &Coin) -> Issue`
+    pub fn of(coin: &Coin) -> Issue {
+        Issue { denom: coin.denom, mint: coin.mint, year: coin.year, material: coin.material }
+    }
+}
+
+/// Rarity
+///
+/// Purpose: Collector's grade of how scarce an issue is, by mintage.
+/// Type: `enum Rarity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rarity {
+    /// 100 000 or more struck.
+    Common,
+    /// 10 000 or more.
+    Uncommon,
+    /// 1000 or more.
+    Scarce,
+    /// 100 or more.
+    Rare,
+    /// 10 or more.
+    VeryRare,
+    /// Fewer than 10.
+    Legendary,
+}
+
+impl Rarity {
+    /// from_mintage
+    ///
+    /// Purpose: The grade for an issue with `mintage` coins struck.
+    /// Type: `fn from_mintage(mintage: u64) -> Rarity`
+    pub fn from_mintage(mintage: u64) -> Rarity {
+        match mintage {
+            100_000.. => Rarity::Common,
+            10_000.. => Rarity::Uncommon,
+            1000.. => Rarity::Scarce,
+            100.. => Rarity::Rare,
+            10.. => Rarity::VeryRare,
+            _ => Rarity::Legendary,
+        }
+    }
+
+    /// premium_percent
+    ///
+    /// Purpose: What collectors pay over face value, as a percentage.
+    /// Type: `fn premium_percent(self) -> u32`
+    pub fn premium_percent(self) -> u32 {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 5,
+            Rarity::Scarce => 25,
+            Rarity::Rare => 100,
+            Rarity::VeryRare => 400,
+            Rarity::Legendary => 2000,
+        }
+    }
+}
+
+/// Appraisal
+///
+/// Purpose: What a collector makes of one coin.
+/// Type: `struct Appraisal`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Appraisal {
+    pub issue: Issue,
+    pub mintage: u64,
+    /// `None` if the issue is unlisted.
+    pub rarity: Option<Rarity>,
+    /// 0 (most common) to 100 (one of a kind), on a log scale of mintage.
+    pub score: f64,
+    pub face: Money,
+    pub premium: Money,
+    /// The most a collector will pay: face plus premium, or melt if higher.
+    pub collector_value: Money,
+}
+
+/// Completeness
+///
+/// Purpose: How much of a set of issues a collection holds.
+/// Type: `struct Completeness`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completeness {
+    pub owned: usize,
+    pub total: usize,
+    pub missing: Vec<Issue>,
+}
+
+impl Completeness {
+    /// percent
+    ///
+    /// Purpose: Share of the set owned, 0–100. An empty set counts as complete.
+    /// Type: `fn percent(&self) -> f64`
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 { 100.0 } else { self.owned as f64 * 100.0 / self.total as f64 }
+    }
+}
+
+/// Catalog
+///
+/// Purpose: Mintage counts for every known issue.
+/// Type: `struct Catalog`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    mintages: BTreeMap<Issue, u64>,
+}
+
+impl Catalog {
+    /// new
+    ///
+    /// Purpose: An empty catalog.
+    /// Type: `fn new() -> Catalog`
+    pub fn new() -> Catalog {
+        Catalog::default()
+    }
+
+    /// standard
+    ///
+    /// Purpose: Mintages for every year of every spec in `specs`, plus the
+    /// Mountain mint's Mithril gold and platinum pieces.
+    /// Type: `fn standard(specs: &SpecBook) -> Catalog`
+    pub fn standard(specs: &SpecBook) -> Catalog {
+        let mut rng = Rng::new(STANDARD_SEED);
+        let mut catalog = Catalog::new();
+        for spec in specs.specs() {
+            // Small change is struck in the largest numbers.
+            let base = match spec.denom {
+                Denomination::Copper => 400_000,
+                Denomination::Silver => 150_000,
+                Denomination::Electrum => 8000,
+                Denomination::Gold => 30_000,
+                Denomination::Platinum => 1500,
+            };
+            for year in spec.years.clone() {
+                let issue = Issue { denom: spec.denom, mint: spec.mint, year, material: spec.material };
+                catalog.record(issue, base / 2 + rng.below(base * 3 / 2));
+            }
+            if spec.mint == Mint::Mountain {
+                for &(denom, most) in MITHRIL_ISSUES.iter().filter(|m| m.0 == spec.denom) {
+                    for year in spec.years.clone() {
+                        let issue = Issue { denom, mint: Mint::Mountain, year, material: Material::Mithril };
+                        catalog.record(issue, 1 + rng.below(most));
+                    }
+                }
+            }
+        }
+        catalog
+    }
+
+    /// record
+    ///
+    /// Purpose: Add `count` struck coins to an issue's mintage.
+    /// Type: `fn record(&mut self, issue: Issue, count: u64)`
+    pub fn record(&mut self, issue: Issue, count: u64) {
+        let mintage = self.mintages.entry(issue).or_insert(0);
+        *mintage = mintage.saturating_add(count);
+    }
+
+    /// mintage
+    ///
+    /// Purpose: How many coins of an issue were struck (0 if unlisted).
+    /// Type: `fn mintage(&self, issue: &Issue) -> u64`
+    pub fn mintage(&self, issue: &Issue) -> u64 {
+        self.mintages.get(issue).copied().unwrap_or(0)
+    }
+
+    /// issues
+    ///
+    /// Purpose: Every listed issue with its mintage, in order.
+    /// Type: `fn issues(&self) -> impl Iterator<Item = (&Issue, &u64)>`
+    pub fn issues(&self) -> impl Iterator<Item = (&Issue, &u64)> {
+        self.mintages.iter()
+    }
+
+    /// appraise
+    ///
+    /// Purpose: Rarity and collector value of a coin in its condition.
+    /// Type: `fn appraise(&self, coin: &Coin) -> Result<Appraisal, MoneyError>`
+    pub fn appraise(&self, coin: &Coin) -> Result<Appraisal, MoneyError> {
+        let issue = Issue::of(coin);
+        let mintage = self.mintage(&issue);
+        let rarity = (mintage > 0).then(|| Rarity::from_mintage(mintage));
+        let score = if mintage == 0 { 0.0 } else { (100.0 - 15.0 * (mintage as f64).log10()).clamp(0.0, 100.0) };
+        let face = coin.value_in_cp();
+        // A rarity premium only counts in full for an uncirculated coin.
+        let percent = rarity.map_or(0, Rarity::premium_percent) * coin.condition.grade.premium_percent();
+        let premium = Money::from_cp((face * percent)?.cp() / 10_000);
+        let collector_value = (face + premium)?.max(coin.melt_value());
+        Ok(Appraisal { issue, mintage, rarity, score, face, premium, collector_value })
+    }
+
+    /// completeness
+    ///
+    /// Purpose: How many of the listed issues matching `in_set` appear among
+    /// `coins`.
+    /// Type: `fn completeness(&self, coins: &[Coin], in_set: impl Fn(&Issue) -> bool) -> Completeness`
+    pub fn completeness(&self, coins: &[Coin], in_set: impl Fn(&Issue) -> bool) -> Completeness {
+        let owned: BTreeSet<Issue> = coins.iter().map(Issue::of).collect();
+        let set: Vec<Issue> = self.mintages.keys().copied().filter(|i| in_set(i)).collect();
+        let missing: Vec<Issue> = set.iter().copied().filter(|i| !owned.contains(i)).collect();
+        Completeness { owned: set.len() - missing.len(), total: set.len(), missing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{Condition, Grade};
+    use crate::test_coins::coin;
+
+    fn mithril_gold(year: u16) -> Coin {
+        Coin { year, ..coin(Denomination::Gold, Material::Mithril, Mint::Mountain) }
+    }
+
+    #[test]
+    fn test_rarity_grades() {
+        assert_eq!(Rarity::from_mintage(250_000), Rarity::Common);
+        assert_eq!(Rarity::from_mintage(10_000), Rarity::Uncommon);
+        assert_eq!(Rarity::from_mintage(999), Rarity::Rare);
+        assert_eq!(Rarity::from_mintage(1), Rarity::Legendary);
+        assert!(Rarity::Legendary > Rarity::Common);
+    }
+
+    #[test]
+    fn test_standard_catalog_lists_every_spec_year_and_mithril_gold() {
+        let specs = SpecBook::standard();
+        let catalog = Catalog::standard(&specs);
+        assert_eq!(catalog, Catalog::standard(&specs));
+        for spec in specs.specs() {
+            for year in spec.years.clone() {
+                assert!(catalog.mintage(&Issue::of(&spec.strike(year))) > 0);
+            }
+        }
+        for year in 1000..=1100 {
+            let mintage = catalog.mintage(&Issue::of(&mithril_gold(year)));
+            assert!((1..=40).contains(&mintage), "{year}: {mintage}");
+        }
+        // Mithril gold was only ever struck in the mountains.
+        let capital = Coin { mint: Mint::Capital, ..mithril_gold(1020) };
+        assert_eq!(catalog.mintage(&Issue::of(&capital)), 0);
+    }
+
+    #[test]
+    fn test_appraisal_and_premiums() {
+        let mut catalog = Catalog::new();
+        let gold = Coin { material: Material::Gold, ..mithril_gold(1020) };
+        catalog.record(Issue::of(&gold), 500_000);
+        catalog.record(Issue::of(&mithril_gold(1020)), 4);
+        catalog.record(Issue::of(&mithril_gold(1020)), 3);
+
+        let common = catalog.appraise(&gold).unwrap();
+        assert_eq!(common.rarity, Some(Rarity::Common));
+        assert_eq!(common.premium, Money::ZERO);
+        assert_eq!(common.collector_value, gold.melt_value().max(Money::from_cp(100)));
+
+        let mithril = catalog.appraise(&mithril_gold(1020)).unwrap();
+        assert_eq!(mithril.mintage, 7);
+        assert_eq!(mithril.issue.to_string(), "Mountain Mithril Gold 1020");
+        assert_eq!(mithril.rarity, Some(Rarity::Legendary));
+        assert_eq!(mithril.premium, Money::from_cp(2000));
+        assert!(mithril.score > common.score);
+        let handled = Coin { condition: Condition { grade: Grade::VeryFine, ..Condition::MINT }, ..mithril_gold(1020) };
+        assert_eq!(catalog.appraise(&handled).unwrap().premium, Money::from_cp(1000));
+
+        let unlisted = catalog.appraise(&mithril_gold(1021)).unwrap();
+        assert_eq!((unlisted.rarity, unlisted.premium, unlisted.score), (None, Money::ZERO, 0.0));
+    }
+
+    #[test]
+    fn test_collection_completeness() {
+        let catalog = Catalog::standard(&SpecBook::standard());
+        let is_mithril_gold = |i: &Issue| i.material == Material::Mithril && i.denom == Denomination::Gold;
+        let coins = [mithril_gold(1000), mithril_gold(1000), mithril_gold(1050), mithril_gold(1200)];
+        let report = catalog.completeness(&coins, is_mithril_gold);
+        assert_eq!((report.owned, report.total), (2, 101));
+        assert_eq!(report.missing.len(), 99);
+        assert!(!report.missing.contains(&Issue::of(&mithril_gold(1050))));
+        assert!((report.percent() - 200.0 / 101.0).abs() < 1e-9);
+        assert_eq!(catalog.completeness(&coins, |_| false).percent(), 100.0);
+    }
+}
@@ -0,0 +1,315 @@
+//! change — paying a price and giving change from a limited set of coins
+//!
+//! `value_breakdown` assumes an endless supply of every denomination. Here we
+//! work with the coins actually held in a `Wallet`.
+//!
+//! Notes:
+//! - Both algorithms are greedy, largest coin first. That is exact (not just
+//!   a heuristic) because each value in the `Denomination::value_in_cp`
+//!   table divides the next larger one (1 | 10 | 50 | 100 | 1000): any pile
+//!   of smaller coins worth at least one large coin contains a sub-pile worth
+//!   exactly that coin, so swapping it for the large coin never hurts.
+//! - Coins are picked by face value, so the greedy picks stay exact; what the
+//!   merchant credits for them comes from a `CoinPolicy` (see `condition.rs`),
+//!   which starts from `Coin::worth`. Plain `purchase` takes every coin at
+//!   its worth.
+//! - Change is only given in full-worth coins, so a merchant never passes a
+//!   debased coin on at face value.
+
+use std::cmp::Reverse;
+
+use crate::condition::CoinPolicy;
+use crate::money::Money;
+use crate::wallet::{Wallet, WalletError};
+use crate::Coin;
+
+/// Payment
+///
+/// Purpose: The coins chosen to pay a price, and how much they overpay it.
+/// Type: `struct Payment`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub coins: Vec<Coin>,
+    pub overpaid: Money,
+}
+
+/// Purchase
+///
+/// Purpose: The result of `purchase`: what the buyer handed over and what
+/// the merchant gave back.
+/// Type: `struct Purchase`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Purchase {
+    pub paid: Vec<Coin>,
+    pub change: Vec<Coin>,
+}
+
+/// largest_first
+///
+/// Purpose: The wallet's coins sorted by face value, largest first.
+/// Type: `fn largest_first(wallet: &Wallet) -> Vec<Coin>`
+fn largest_first(wallet: &Wallet) -> Vec<Coin> {
+    let mut coins: Vec<Coin> = wallet.coins().copied().collect();
+    coins.sort_by_key(|c| Reverse(c.value_in_cp()));
+    coins
+}
+
+/// make_change
+///
+/// Purpose: Choose the fewest coins from `inventory` worth exactly `amount`.
+/// The inventory is not modified.
+/// Parameters: `inventory: &Wallet`, `amount: Money`
+/// Returns: the coins, or `InsufficientFunds` / `NoExactAmount`.
+/// Type: `fn make_change(inventory: &Wallet, amount: Money) -> Result<Vec<Coin>, WalletError>`
+pub fn make_change(inventory: &Wallet, amount: Money) -> Result<Vec<Coin>, WalletError> {
+    let available = inventory.value_in_cp()?;
+    if available < amount {
+        return Err(WalletError::InsufficientFunds { requested: amount, available });
+    }
+
+    let mut remaining = amount;
+    let mut picked = Vec::new();
+    for coin in largest_first(inventory) {
+        if let Ok(rest) = remaining - coin.value_in_cp() {
+            remaining = rest;
+            picked.push(coin);
+        }
+    }
+    if remaining != Money::ZERO {
+        return Err(WalletError::NoExactAmount { requested: amount });
+    }
+    Ok(picked)
+}
+
+/// choose_payment
+///
+/// Purpose: Choose coins from `wallet` worth at least `price` with the
+/// smallest overpayment; among those, use the fewest coins. The wallet is
+/// not modified.
+/// Parameters: `wallet: &Wallet`, `price: Money`
+/// Returns: the `Payment`, or `InsufficientFunds` if the wallet is too light.
+/// Type: `fn choose_payment(wallet: &Wallet, price: Money) -> Result<Payment, WalletError>`
+pub fn choose_payment(wallet: &Wallet, price: Money) -> Result<Payment, WalletError> {
+    let total = wallet.value_in_cp()?;
+    let Ok(budget) = total - price else {
+        return Err(WalletError::InsufficientFunds { requested: price, available: total });
+    };
+
+    // Smallest overpayment == keep as much as possible without keeping more
+    // than `budget`. Whatever room is left unfilled is the overpayment.
+    let mut room = budget;
+    for coin in largest_first(wallet) {
+        if let Ok(rest) = room - coin.value_in_cp() {
+            room = rest;
+        }
+    }
+    let to_pay = (price + room)?;
+    let coins = make_change(wallet, to_pay)?;
+    Ok(Payment { coins, overpaid: room })
+}
+
+/// purchase
+///
+/// Purpose: Buy something for `price`: the buyer pays with `choose_payment`,
+/// the merchant adds those coins to their inventory and gives back any
+/// overpayment with `make_change`. If the merchant can't make exact change,
+/// nothing changes hands.
+/// Parameters: `buyer: &mut Wallet`, `merchant: &mut Wallet`, `price: Money`
+/// Returns: the coins paid and received as change, or a `WalletError`.
+/// Type: `fn purchase(buyer: &mut Wallet, merchant: &mut Wallet, price: Money) -> Result<Purchase, WalletError>`
+pub fn purchase(buyer: &mut Wallet, merchant: &mut Wallet, price: Money) -> Result<Purchase, WalletError> {
+    purchase_with(buyer, merchant, price, &CoinPolicy::default())
+}
+
+/// purchase_with
+///
+/// Purpose: Like `purchase`, but the merchant judges coins by `policy`:
+/// refused coins are never offered, and discounted coins count for less,
+/// so the buyer may have to hand over more.
+/// Every coin wears once (`Coin::circulate`) as it changes hands; the coins
+/// listed in the returned `Purchase` are as they were handed over.
+/// Parameters: `buyer`, `merchant`, `price`, `policy: &CoinPolicy`
+/// Returns: the coins paid and received as change, or a `WalletError`.
+/// Type: `fn purchase_with(buyer: &mut Wallet, merchant: &mut Wallet, price: Money, policy: &CoinPolicy) -> Result<Purchase, WalletError>`
+pub fn purchase_with(buyer: &mut Wallet, merchant: &mut Wallet, price: Money, policy: &CoinPolicy) -> Result<Purchase, WalletError> {
+    let acceptable: Wallet = policy.tender(&buyer.coins().copied().collect::<Vec<_>>())?.accepted.into_iter().collect();
+
+    // Ask for more until the merchant credits the whole price. Each round
+    // asks for strictly more, so this ends once the buyer runs out.
+    let mut asking = price;
+    let (payment, credited) = loop {
+        let payment = choose_payment(&acceptable, asking)?;
+        let credited = policy.tender(&payment.coins)?.value;
+        match price - credited {
+            Ok(short) if short > Money::ZERO => asking = (asking + short)?,
+            _ => break (payment, credited),
+        }
+    };
+
+    let mut till = merchant.clone();
+    for coin in &payment.coins {
+        till.deposit(coin.circulated());
+    }
+    let sound: Wallet = till.coins().filter(|c| c.worth() == c.value_in_cp()).copied().collect();
+    let change = make_change(&sound, (credited - price)?)?;
+    till.withdraw_all(&change)?;
+
+    buyer.withdraw_all(&payment.coins)?;
+    for coin in &change {
+        buyer.deposit(coin.circulated());
+    }
+    *merchant = till;
+    Ok(Purchase { paid: payment.coins, change })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::Condition;
+    use crate::test_coins;
+    use crate::{Denomination, Material, Mint};
+
+    fn coin(denom: Denomination) -> Coin {
+        test_coins::coin(denom, Material::Copper, Mint::Capital)
+    }
+
+    fn value(coins: &[Coin]) -> u64 {
+        coins.iter().map(|c| c.value_in_cp().cp()).sum()
+    }
+
+    /// Small deterministic wallets: up to 10 coins with mixed denominations.
+    fn sample_wallets() -> Vec<Vec<Coin>> {
+        let mut seed: u32 = 12345;
+        (0..40)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let n = (seed >> 16) % 10 + 1;
+                (0..n)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                        coin(Denomination::ALL[((seed >> 16) % 5) as usize])
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// (smallest overpayment, fewest coins at that overpayment), by trying every subset.
+    fn brute_force(coins: &[Coin], price: u64) -> Option<(u64, usize)> {
+        (0u32..1 << coins.len())
+            .filter_map(|mask| {
+                let chosen: Vec<Coin> = (0..coins.len()).filter(|i| mask & (1 << i) != 0).map(|i| coins[i]).collect();
+                let v = value(&chosen);
+                (v >= price).then(|| (v - price, chosen.len()))
+            })
+            .min()
+    }
+
+    #[test]
+    fn test_denominations_form_a_divisibility_chain() {
+        for pair in Denomination::ALL.windows(2) {
+            assert_eq!(pair[1].value_in_cp() % pair[0].value_in_cp(), 0);
+        }
+    }
+
+    #[test]
+    fn test_choose_payment_matches_brute_force() {
+        for coins in sample_wallets() {
+            let wallet: Wallet = coins.iter().copied().collect();
+            for price in [0, 1, 9, 35, 60, 99, 150, 777, 1234, 5000] {
+                let expected = brute_force(&coins, price);
+                match choose_payment(&wallet, Money::from_cp(price)) {
+                    Ok(p) => {
+                        assert_eq!(value(&p.coins), price + p.overpaid.cp());
+                        assert_eq!(Some((p.overpaid.cp(), p.coins.len())), expected, "{coins:?} @ {price}");
+                    }
+                    Err(WalletError::InsufficientFunds { .. }) => assert_eq!(expected, None),
+                    Err(e) => panic!("unexpected {e}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_make_change_matches_brute_force() {
+        for coins in sample_wallets() {
+            let wallet: Wallet = coins.iter().copied().collect();
+            for amount in [1, 10, 40, 60, 110, 150, 1001] {
+                let expected = brute_force(&coins, amount).filter(|&(over, _)| over == 0).map(|(_, n)| n);
+                let got = make_change(&wallet, Money::from_cp(amount)).ok();
+                assert_eq!(got.as_ref().map(Vec::len), expected, "{coins:?} @ {amount}");
+                if let Some(picked) = got {
+                    assert_eq!(value(&picked), amount);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_purchase_moves_coins_both_ways() {
+        let mut buyer: Wallet = [coin(Denomination::Gold)].into_iter().collect();
+        let mut merchant: Wallet = [coin(Denomination::Electrum), coin(Denomination::Silver), coin(Denomination::Silver)]
+            .into_iter()
+            .collect();
+        let deal = purchase(&mut buyer, &mut merchant, Money::from_cp(30)).unwrap();
+        assert_eq!(deal.paid, vec![coin(Denomination::Gold)]);
+        assert_eq!(deal.change, vec![coin(Denomination::Electrum), coin(Denomination::Silver), coin(Denomination::Silver)]);
+        assert_eq!(buyer.value_in_cp(), Ok(Money::from_cp(70)));
+        assert_eq!(merchant.value_in_cp(), Ok(Money::from_cp(100)));
+
+        // Paying 5 cp with a silver piece needs 5 cp back; the merchant has no copper.
+        let mut poor: Wallet = [coin(Denomination::Copper); 5].into_iter().collect();
+        assert_eq!(
+            purchase(&mut buyer, &mut merchant, Money::from_cp(5)),
+            Err(WalletError::NoExactAmount { requested: Money::from_cp(5) })
+        );
+        assert_eq!(purchase(&mut poor, &mut merchant, Money::from_cp(5)).unwrap().change, vec![]);
+        assert!(poor.is_empty());
+    }
+
+    #[test]
+    fn test_purchase_credits_debased_coins_at_their_worth() {
+        let debased = |denom| Coin { condition: Condition::debased(50), ..coin(denom) };
+        let mut buyer: Wallet = [debased(Denomination::Gold)].into_iter().collect();
+        let mut merchant: Wallet = [debased(Denomination::Silver), coin(Denomination::Silver), coin(Denomination::Silver)]
+            .into_iter()
+            .chain([coin(Denomination::Electrum)])
+            .collect();
+        // The gold piece passes for 50 cp, so 20 cp comes back, and never in the debased silver.
+        let deal = purchase(&mut buyer, &mut merchant, Money::from_cp(30)).unwrap();
+        assert_eq!(deal.change, vec![coin(Denomination::Silver), coin(Denomination::Silver)]);
+        assert_eq!(buyer.worth(), Ok(Money::from_cp(20)));
+        assert_eq!(merchant.worth(), Ok(Money::from_cp(5 + 50 + 50)));
+    }
+
+    #[test]
+    fn test_purchase_with_picky_merchant() {
+        use crate::condition::Grade;
+        let worn = |denom| Coin { condition: Condition { grade: Grade::Worn, purity_pct: 100, uses: 300 }, ..coin(denom) };
+        let policy = CoinPolicy {
+            refuse_below: Grade::Good,
+            discount_below: Grade::Uncirculated,
+            discount_pct: 50,
+            ..CoinPolicy::default()
+        };
+        let worn_silver = Coin { condition: Condition { grade: Grade::Fine, ..Condition::MINT }, ..coin(Denomination::Silver) };
+
+        // The worn gold piece is refused; the fine silver only counts for 5 cp.
+        let mut buyer: Wallet = [worn(Denomination::Gold), worn_silver, worn_silver, coin(Denomination::Silver)].into_iter().collect();
+        let mut merchant: Wallet = [coin(Denomination::Copper); 5].into_iter().collect();
+        let deal = purchase_with(&mut buyer, &mut merchant, Money::from_cp(15), &policy).unwrap();
+        assert!(!deal.paid.contains(&worn(Denomination::Gold)));
+        let credited = policy.tender(&deal.paid).unwrap().value.cp();
+        assert_eq!(credited - value(&deal.change), 15);
+        assert_eq!(buyer.value_in_cp().unwrap().cp(), 130 - value(&deal.paid) + value(&deal.change));
+
+        // Coins wear as they change hands.
+        assert!(merchant.coins().filter(|c| c.denom == Denomination::Silver).all(|c| c.condition.uses == 1));
+
+        let mut broke: Wallet = [worn(Denomination::Gold)].into_iter().collect();
+        assert!(matches!(
+            purchase_with(&mut broke, &mut merchant, Money::from_cp(1), &policy),
+            Err(WalletError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+//! condition — coin wear, purity and what merchants make of them
+//!
+//! Every coin carries a `Condition`: its grade (uncirculated down to worn),
+//! the purity of its metal, and how many times it has changed hands. Coins
+//! wear as they circulate, losing a grade every `USES_PER_GRADE` uses.
+//!
+//! Notes:
+//! - Face value never changes, but a debased coin only passes for its pure
+//!   share (`Coin::worth`); totals, wallets, ledgers and the economy count
+//!   coins at that worth. A worn coin is still worth its face.
+//! - Wear and purity lower what the metal is worth (`melt.rs`), what
+//!   collectors pay (`catalog.rs`), and what a picky merchant will credit.
+//! - A `CoinPolicy` lets a merchant refuse coins below one grade or purity
+//!   and discount them below another grade.
+//! - Worth and discounts round down, in the holder's disfavour.
+
+use std::fmt;
+
+use crate::Coin;
+use crate::money::{Money, MoneyError};
+
+/// A coin drops one grade after this many uses.
+pub const USES_PER_GRADE: u32 = 25;
+
+/// Grade
+///
+/// Purpose: How worn a coin is. Ordered worst first, so `grade < Grade::Fine`
+/// means "worse than fine".
+/// Type: `enum Grade`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grade {
+    Worn,
+    Good,
+    Fine,
+    VeryFine,
+    ExtremelyFine,
+    Uncirculated,
+}
+
+impl Grade {
+    /// Every grade, worst first.
+    pub const ALL: [Grade; 6] = [Grade::Worn, Grade::Good, Grade::Fine, Grade::VeryFine, Grade::ExtremelyFine, Grade::Uncirculated];
+
+    /// worse
+    ///
+    /// Purpose: The next grade down (`Worn` stays `Worn`).
+    /// Type: `fn worse(self) -> Grade`
+    pub fn worse(self) -> Grade {
+        match self {
+            Grade::Uncirculated => Grade::ExtremelyFine,
+            Grade::ExtremelyFine => Grade::VeryFine,
+            Grade::VeryFine => Grade::Fine,
+            Grade::Fine => Grade::Good,
+            Grade::Good | Grade::Worn => Grade::Worn,
+        }
+    }
+
+    /// metal_percent
+    ///
+    /// Purpose: Share of the original metal left after this much wear.
+    /// Type: `fn metal_percent(self) -> u32`
+    pub fn metal_percent(self) -> u32 {
+        match self {
+            Grade::Uncirculated => 100,
+            Grade::ExtremelyFine => 99,
+            Grade::VeryFine => 98,
+            Grade::Fine => 96,
+            Grade::Good => 93,
+            Grade::Worn => 90,
+        }
+    }
+
+    /// premium_percent
+    ///
+    /// Purpose: Share of a rarity premium collectors still pay at this grade.
+    /// Type: `fn premium_percent(self) -> u32`
+    pub fn premium_percent(self) -> u32 {
+        match self {
+            Grade::Uncirculated => 100,
+            Grade::ExtremelyFine => 75,
+            Grade::VeryFine => 50,
+            Grade::Fine => 25,
+            Grade::Good => 10,
+            Grade::Worn => 0,
+        }
+    }
+}
+
+/// Condition
+///
+/// Purpose: The state of one physical coin.
+/// Type: `struct Condition`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub grade: Grade,
+    /// Fine metal content, 0–100%. Debased issues are below 100.
+    pub purity_pct: u8,
+    /// How many times the coin has changed hands.
+    pub uses: u32,
+}
+
+impl Condition {
+    /// Fresh from the mint: uncirculated, full purity, never used.
+    pub const MINT: Condition = Condition { grade: Grade::Uncirculated, purity_pct: 100, uses: 0 };
+
+    /// debased
+    ///
+    /// Purpose: A freshly struck coin of reduced purity.
+    /// Type: `fn debased(purity_pct: u8) -> Condition`
+    pub fn debased(purity_pct: u8) -> Condition {
+        Condition { purity_pct: purity_pct.min(100), ..Condition::MINT }
+    }
+}
+
+impl Coin {
+    /// worth
+    ///
+    /// Purpose: What this coin passes for: its face value scaled by its
+    /// purity, rounded down. Wear does not lower it.
+    /// Type: `fn worth(&self) -> Money`
+    pub fn worth(&self) -> Money {
+        let face = self.value_in_cp().cp();
+        Money::from_cp(face * u64::from(self.condition.purity_pct.min(100)) / 100)
+    }
+
+    /// circulate
+    ///
+    /// Purpose: Record one use of the coin, wearing it down a grade every
+    /// `USES_PER_GRADE` uses.
+    /// Type: `fn circulate(&mut self)`
+    pub fn circulate(&mut self) {
+        self.condition.uses = self.condition.uses.saturating_add(1);
+        if self.condition.uses.is_multiple_of(USES_PER_GRADE) {
+            self.condition.grade = self.condition.grade.worse();
+        }
+    }
+
+    /// circulated
+    ///
+    /// Purpose: This coin after one more use.
+    /// Type: `fn circulated(self) -> Coin`
+    pub fn circulated(mut self) -> Coin {
+        self.circulate();
+        self
+    }
+}
+
+/// Refusal
+///
+/// Purpose: Why a merchant will not take a coin.
+/// Type: `enum Refusal`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    TooWorn { grade: Grade, minimum: Grade },
+    Debased { purity_pct: u8, minimum: u8 },
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::TooWorn { grade, minimum } => write!(f, "too worn ({grade:?}, needs {minimum:?} or better)"),
+            Refusal::Debased { purity_pct, minimum } => write!(f, "debased ({purity_pct}% pure, needs {minimum}%)"),
+        }
+    }
+}
+
+/// Verdict
+///
+/// Purpose: What a merchant makes of one coin.
+/// Type: `enum Verdict`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Taken at face value.
+    Accepted(Money),
+    /// Taken, but credited for less than face.
+    Discounted(Money),
+    Refused(Refusal),
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Accepted(value) => write!(f, "accepted at {value}"),
+            Verdict::Discounted(value) => write!(f, "discounted to {value}"),
+            Verdict::Refused(why) => write!(f, "refused: {why}"),
+        }
+    }
+}
+
+/// Tender
+///
+/// Purpose: The result of offering coins to a merchant.
+/// Type: `struct Tender`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tender {
+    pub accepted: Vec<Coin>,
+    pub refused: Vec<(Coin, Refusal)>,
+    /// What the merchant credits for the accepted coins.
+    pub value: Money,
+}
+
+/// CoinPolicy
+///
+/// Purpose: Which coins a merchant takes, and at what value.
+/// Type: `struct CoinPolicy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoinPolicy {
+    /// Coins worse than this are refused.
+    pub refuse_below: Grade,
+    /// Coins worse than this are discounted by `discount_pct`.
+    pub discount_below: Grade,
+    pub discount_pct: u32,
+    /// Coins less pure than this are refused.
+    pub min_purity_pct: u8,
+}
+
+impl Default for CoinPolicy {
+    /// Takes any coin at what it is worth.
+    fn default() -> Self {
+        CoinPolicy { refuse_below: Grade::Worn, discount_below: Grade::Worn, discount_pct: 0, min_purity_pct: 0 }
+    }
+}
+
+impl CoinPolicy {
+    /// assess
+    ///
+    /// Purpose: Decide whether to take `coin` and what to credit for it:
+    /// its `worth`, less the discount if it is worn.
+    /// Type: `fn assess(&self, coin: &Coin) -> Result<Verdict, MoneyError>`
+    pub fn assess(&self, coin: &Coin) -> Result<Verdict, MoneyError> {
+        let Condition { grade, purity_pct, .. } = coin.condition;
+        if grade < self.refuse_below {
+            return Ok(Verdict::Refused(Refusal::TooWorn { grade, minimum: self.refuse_below }));
+        }
+        if purity_pct < self.min_purity_pct {
+            return Ok(Verdict::Refused(Refusal::Debased { purity_pct, minimum: self.min_purity_pct }));
+        }
+        let mut value = coin.worth();
+        if grade < self.discount_below {
+            value = Money::from_cp((value * (100 - self.discount_pct.min(100)))?.cp() / 100);
+        }
+        Ok(if value == coin.value_in_cp() { Verdict::Accepted(value) } else { Verdict::Discounted(value) })
+    }
+
+    /// tender
+    ///
+    /// Purpose: Offer `coins` to the merchant and total what is credited.
+    /// Type: `fn tender(&self, coins: &[Coin]) -> Result<Tender, MoneyError>`
+    pub fn tender(&self, coins: &[Coin]) -> Result<Tender, MoneyError> {
+        let mut tender = Tender { accepted: Vec::new(), refused: Vec::new(), value: Money::ZERO };
+        for coin in coins {
+            match self.assess(coin)? {
+                Verdict::Accepted(value) | Verdict::Discounted(value) => {
+                    tender.accepted.push(*coin);
+                    tender.value = (tender.value + value)?;
+                }
+                Verdict::Refused(why) => tender.refused.push((*coin, why)),
+            }
+        }
+        Ok(tender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_coins;
+    use crate::{total_value_in_cp, Denomination, Material, Mint};
+
+    fn coin(condition: Condition) -> Coin {
+        Coin { condition, ..test_coins::coin(Denomination::Gold, Material::Gold, Mint::Capital) }
+    }
+
+    #[test]
+    fn test_coins_wear_with_use() {
+        let mut c = coin(Condition::MINT);
+        for _ in 0..USES_PER_GRADE - 1 {
+            c.circulate();
+        }
+        assert_eq!(c.condition.grade, Grade::Uncirculated);
+        c = c.circulated();
+        assert_eq!(c.condition.grade, Grade::ExtremelyFine);
+        for _ in 0..USES_PER_GRADE * 10 {
+            c.circulate();
+        }
+        assert_eq!(c.condition, Condition { grade: Grade::Worn, purity_pct: 100, uses: USES_PER_GRADE * 11 });
+        // Face value and worth are untouched by wear.
+        assert_eq!(c.value_in_cp(), Money::from_cp(100));
+        assert_eq!(c.worth(), Money::from_cp(100));
+    }
+
+    #[test]
+    fn test_debased_coins_are_worth_their_purity() {
+        let debased = coin(Condition::debased(75));
+        assert_eq!(debased.value_in_cp(), Money::from_cp(100));
+        assert_eq!(debased.worth(), Money::from_cp(75));
+        let copper = Coin { denom: Denomination::Copper, ..coin(Condition::debased(99)) };
+        assert_eq!(copper.worth(), Money::ZERO);
+        assert_eq!(total_value_in_cp(&[debased, coin(Condition::MINT)]), Ok(Money::from_cp(175)));
+    }
+
+    #[test]
+    fn test_wear_and_purity_lower_melt_value() {
+        let fresh = coin(Condition::MINT);
+        let worn = coin(Condition { grade: Grade::Worn, ..Condition::MINT });
+        let debased = coin(Condition::debased(50));
+        assert!(worn.melt_value() < fresh.melt_value());
+        assert!(debased.melt_value().cp().abs_diff(fresh.melt_value().cp() / 2) <= 1);
+        assert_eq!(Condition::debased(150).purity_pct, 100);
+    }
+
+    #[test]
+    fn test_merchant_policy() {
+        let policy = CoinPolicy {
+            refuse_below: Grade::Good,
+            discount_below: Grade::Fine,
+            discount_pct: 20,
+            min_purity_pct: 60,
+        };
+        let m = Money::from_cp;
+        let at = |grade, purity_pct| coin(Condition { grade, purity_pct, uses: 0 });
+        assert_eq!(policy.assess(&at(Grade::Fine, 100)), Ok(Verdict::Accepted(m(100))));
+        assert_eq!(policy.assess(&at(Grade::Good, 100)), Ok(Verdict::Discounted(m(80))));
+        assert_eq!(policy.assess(&at(Grade::Good, 75)), Ok(Verdict::Discounted(m(60))));
+        assert_eq!(
+            policy.assess(&at(Grade::Worn, 100)),
+            Ok(Verdict::Refused(Refusal::TooWorn { grade: Grade::Worn, minimum: Grade::Good }))
+        );
+        assert_eq!(
+            policy.assess(&at(Grade::Uncirculated, 50)),
+            Ok(Verdict::Refused(Refusal::Debased { purity_pct: 50, minimum: 60 }))
+        );
+
+        let tender = policy.tender(&[at(Grade::Fine, 100), at(Grade::Worn, 100), at(Grade::Good, 90)]).unwrap();
+        assert_eq!(tender.accepted.len(), 2);
+        assert_eq!(tender.refused.len(), 1);
+        assert_eq!(tender.value, m(100 + 72));
+
+        let lenient = CoinPolicy::default().tender(&[at(Grade::Worn, 10), at(Grade::Worn, 100)]).unwrap();
+        assert_eq!(lenient.value, m(10 + 100));
+
+        assert_eq!(Verdict::Discounted(m(80)).to_string(), "discounted to 80 cp");
+        assert_eq!(
+            Verdict::Refused(Refusal::Debased { purity_pct: 50, minimum: 60 }).to_string(),
+            "refused: debased (50% pure, needs 60%)"
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! currency — denomination tables loaded from config files
+//!
+//! The `Denomination` enum hardcodes one currency. A `CurrencyTable` holds
+//! any set of denominations (name, display code, value in the base unit) and
+//! can be read from a small TOML file:
+//!
+//! ```toml
+//! name = "Dwarven marks"
+//!
+//! [[denomination]]
+//! name = "Iron bit"
+//! code = "ib"
+//! value = 1
+//! ```
+//!
+//! Notes:
+//! - Only the TOML needed for such tables is understood: top-level `name`,
+//!   `[[denomination]]` entries, `"strings"`, integers and `#` comments.
+//! - Values must be positive and unique; codes must be unique.
+//! - `breakdown` always finds the fewest coins. Greedy (largest first) is
+//!   only exact for some tables (1/3/4 would break 6 into 4+1+1, not 3+3),
+//!   so it searches over amounts small enough to matter (see `search_limit`).
+
+use std::cmp::Reverse;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::money::Money;
+use crate::Denomination;
+
+/// CurrencyDenomination
+///
+/// Purpose: One row of a currency table.
+/// Type: `struct CurrencyDenomination`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyDenomination {
+    pub name: String,
+    pub code: String,
+    pub value: Money,
+}
+
+/// CurrencyTable
+///
+/// Purpose: A validated set of denominations, kept largest value first.
+/// Type: `struct CurrencyTable`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyTable {
+    name: String,
+    denominations: Vec<CurrencyDenomination>,
+}
+
+/// CurrencyError
+///
+/// Purpose: Why a currency table could not be loaded or is invalid.
+/// Type: `enum CurrencyError`
+#[derive(Debug)]
+pub enum CurrencyError {
+    /// The config file could not be read.
+    Io(io::Error),
+    /// A line of the config file could not be understood.
+    Parse { line: usize, message: String },
+    /// Denomination number `index` (from 1) has no `field`.
+    MissingField { index: usize, field: &'static str },
+    /// The table has no denominations at all.
+    Empty,
+    /// A denomination's value is zero or negative.
+    NotPositive { code: String },
+    /// Two denominations share a value.
+    DuplicateValue { value: Money },
+    /// Two denominations share a display code.
+    DuplicateCode { code: String },
+    /// The values are so far apart that `breakdown` can't search them.
+    TooFarApart { smallest: Money, largest: Money },
+}
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrencyError::Io(e) => write!(f, "cannot read currency file: {e}"),
+            CurrencyError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            CurrencyError::MissingField { index, field } => {
+                write!(f, "denomination #{index} is missing `{field}`")
+            }
+            CurrencyError::Empty => write!(f, "currency has no denominations"),
+            CurrencyError::NotPositive { code } => write!(f, "value of `{code}` must be positive"),
+            CurrencyError::DuplicateValue { value } => write!(f, "two denominations are worth {value}"),
+            CurrencyError::DuplicateCode { code } => write!(f, "display code `{code}` is used twice"),
+            CurrencyError::TooFarApart { smallest, largest } => {
+                write!(f, "values from {smallest} to {largest} are too far apart to make change")
+            }
+        }
+    }
+}
+
+impl Error for CurrencyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CurrencyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CurrencyError {
+    fn from(e: io::Error) -> Self {
+        CurrencyError::Io(e)
+    }
+}
+
+/// Breakdown
+///
+/// Purpose: An amount split into counts of each denomination, largest first.
+/// `remainder` is whatever the smallest denomination could not cover (zero
+/// whenever the table has a value-1 coin).
+/// Type: `struct Breakdown<'a>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakdown<'a> {
+    pub parts: Vec<(&'a CurrencyDenomination, u64)>,
+    pub remainder: Money,
+}
+
+impl CurrencyTable {
+    /// new
+    ///
+    /// Purpose: Build and validate a table from its rows (in any order).
+    /// Returns: the table, or the first validation problem found.
+    /// Type: `fn new(name: &str, denominations: Vec<CurrencyDenomination>) -> Result<CurrencyTable, CurrencyError>`
+    pub fn new(name: &str, mut denominations: Vec<CurrencyDenomination>) -> Result<CurrencyTable, CurrencyError> {
+        if denominations.is_empty() {
+            return Err(CurrencyError::Empty);
+        }
+        if let Some(d) = denominations.iter().find(|d| d.value == Money::ZERO) {
+            return Err(CurrencyError::NotPositive { code: d.code.clone() });
+        }
+        denominations.sort_by_key(|d| std::cmp::Reverse(d.value));
+        if let Some(pair) = denominations.windows(2).find(|p| p[0].value == p[1].value) {
+            return Err(CurrencyError::DuplicateValue { value: pair[0].value });
+        }
+        for (i, d) in denominations.iter().enumerate() {
+            if denominations[..i].iter().any(|e| e.code == d.code) {
+                return Err(CurrencyError::DuplicateCode { code: d.code.clone() });
+            }
+        }
+        let values: Vec<u64> = denominations.iter().map(|d| d.value.cp()).collect();
+        if search_limit(&values).is_none() {
+            let (largest, smallest) = (denominations[0].value, denominations[denominations.len() - 1].value);
+            return Err(CurrencyError::TooFarApart { smallest, largest });
+        }
+        Ok(CurrencyTable { name: name.to_string(), denominations })
+    }
+
+    /// standard
+    ///
+    /// Purpose: The built-in table matching the `Denomination` enum.
+    /// Type: `fn standard() -> CurrencyTable`
+    pub fn standard() -> CurrencyTable {
+        let rows = Denomination::ALL
+            .iter()
+            .map(|d| CurrencyDenomination {
+                name: format!("{d:?}"),
+                code: d.display_code().to_string(),
+                value: Money::from_cp(u64::from(d.value_in_cp())),
+            })
+            .collect();
+        CurrencyTable::new("Standard", rows).expect("built-in denominations are valid")
+    }
+
+    /// load
+    ///
+    /// Purpose: Read and validate a table from a TOML file.
+    /// Type: `fn load(path: &str) -> Result<CurrencyTable, CurrencyError>`
+    pub fn load(path: &str) -> Result<CurrencyTable, CurrencyError> {
+        CurrencyTable::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// from_toml_str
+    ///
+    /// Purpose: Parse and validate a table from TOML text (see module docs).
+    /// Type: `fn from_toml_str(text: &str) -> Result<CurrencyTable, CurrencyError>`
+    pub fn from_toml_str(text: &str) -> Result<CurrencyTable, CurrencyError> {
+        // Each entry collects optional (name, code, value) until validated.
+        type Row = (Option<String>, Option<String>, Option<i64>);
+        let mut table_name = String::from("Unnamed");
+        let mut rows: Vec<Row> = Vec::new();
+
+        for (i, raw) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let err = |message: &str| CurrencyError::Parse { line: line_no, message: message.to_string() };
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[denomination]]" {
+                rows.push((None, None, None));
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(err("only [[denomination]] tables are supported"));
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| err("expected `key = value`"))?;
+            let value = TomlValue::parse(value.trim()).ok_or_else(|| err("expected a \"string\" or an integer"))?;
+            match (rows.last_mut(), key.trim(), value) {
+                (None, "name", TomlValue::Str(s)) => table_name = s,
+                (Some(row), "name", TomlValue::Str(s)) => row.0 = Some(s),
+                (Some(row), "code", TomlValue::Str(s)) => row.1 = Some(s),
+                (Some(row), "value", TomlValue::Int(n)) => row.2 = Some(n),
+                (None, "code" | "value", _) => return Err(err("must come after [[denomination]]")),
+                (_, "name" | "code", _) => return Err(err("expected a string")),
+                (Some(_), "value", _) => return Err(err("expected an integer")),
+                (_, key, _) => return Err(err(&format!("unknown key `{key}`"))),
+            }
+        }
+
+        let mut denominations = Vec::new();
+        for (i, (name, code, value)) in rows.into_iter().enumerate() {
+            let index = i + 1;
+            let name = name.ok_or(CurrencyError::MissingField { index, field: "name" })?;
+            let code = code.ok_or(CurrencyError::MissingField { index, field: "code" })?;
+            let value = value.ok_or(CurrencyError::MissingField { index, field: "value" })?;
+            let value = u64::try_from(value).map_err(|_| CurrencyError::NotPositive { code: code.clone() })?;
+            denominations.push(CurrencyDenomination { name, code, value: Money::from_cp(value) });
+        }
+        CurrencyTable::new(&table_name, denominations)
+    }
+
+    /// name
+    ///
+    /// Purpose: The currency's display name.
+    /// Type: `fn name(&self) -> &str`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// denominations
+    ///
+    /// Purpose: All denominations, largest value first.
+    /// Type: `fn denominations(&self) -> &[CurrencyDenomination]`
+    pub fn denominations(&self) -> &[CurrencyDenomination] {
+        &self.denominations
+    }
+
+    /// breakdown
+    ///
+    /// Purpose: Split an amount into the fewest coins of unlimited supply,
+    /// largest denomination first (like `value_breakdown`, for any table).
+    /// Every denomination appears in `parts`, including zero counts.
+    /// Type: `fn breakdown(&self, amount: Money) -> Breakdown<'_>`
+    pub fn breakdown(&self, amount: Money) -> Breakdown<'_> {
+        let values: Vec<u64> = self.denominations.iter().map(|d| d.value.cp()).collect();
+        let (counts, remainder) = match search_limit(&values) {
+            Some(limit) if limit > 0 => fewest_coins(&values, amount.cp(), limit),
+            _ => greedy(&values, amount.cp()),
+        };
+        let parts = self.denominations.iter().zip(counts).collect();
+        Breakdown { parts, remainder: Money::from_cp(remainder) }
+    }
+}
+
+/// Largest amount `breakdown` will search over; tables that need more are
+/// rejected by `CurrencyTable::new`.
+const MAX_SEARCH: u64 = 1 << 20;
+
+/// search_limit
+///
+/// Purpose: How much of a fewest-coins breakdown can be in coins other than
+/// the largest, given `values` largest first. With `g = gcd(v, largest)`,
+/// `largest / g` coins of value `v` are worth `v / g` largest coins, which
+/// is fewer, so no best breakdown holds that many.
+/// Returns: `Some(0)` when each value divides the next larger one (greedy is
+/// already exact), `None` if the limit is over `MAX_SEARCH`.
+/// Type: `fn search_limit(values: &[u64]) -> Option<u64>`
+fn search_limit(values: &[u64]) -> Option<u64> {
+    if values.windows(2).all(|p| p[0] % p[1] == 0) {
+        return Some(0);
+    }
+    let largest = values[0];
+    let mut limit: u64 = 0;
+    for &v in &values[1..] {
+        let most = v.checked_mul(largest / gcd(v, largest) - 1)?;
+        limit = limit.checked_add(most)?;
+    }
+    (limit <= MAX_SEARCH).then_some(limit)
+}
+
+/// greedy
+///
+/// Purpose: Take as many of each value as fit, largest first.
+/// Returns: the count of each value and what is left over.
+/// Type: `fn greedy(values: &[u64], amount: u64) -> (Vec<u64>, u64)`
+fn greedy(values: &[u64], amount: u64) -> (Vec<u64>, u64) {
+    let mut left = amount;
+    let counts = values
+        .iter()
+        .map(|&v| {
+            let count = left / v;
+            left %= v;
+            count
+        })
+        .collect();
+    (counts, left)
+}
+
+/// fewest_coins
+///
+/// Purpose: Cover as much of `amount` as possible with the fewest coins.
+/// Every part of up to `limit` made of the smaller values is tried, with
+/// the largest value covering the rest.
+/// Returns: the count of each value and what is left over.
+/// Type: `fn fewest_coins(values: &[u64], amount: u64, limit: u64) -> (Vec<u64>, u64)`
+fn fewest_coins(values: &[u64], amount: u64, limit: u64) -> (Vec<u64>, u64) {
+    let (largest, smaller) = (values[0], &values[1..]);
+    let top = limit.min(amount) as usize;
+    // best[s]: the fewest smaller coins worth exactly s, and one of them.
+    let mut best: Vec<Option<(u64, usize)>> = vec![None; top + 1];
+    best[0] = Some((0, 0));
+    for s in 1..=top {
+        best[s] = smaller
+            .iter()
+            .enumerate()
+            .filter(|&(_, &v)| v <= s as u64)
+            .filter_map(|(i, &v)| best[s - v as usize].map(|(n, _)| (n + 1, i)))
+            .min();
+    }
+
+    let covered = |s: usize| s as u64 + (amount - s as u64) / largest * largest;
+    let coins = |s: usize| best[s].map_or(0, |(n, _)| n) + (amount - s as u64) / largest;
+    let mut s = (0..=top)
+        .filter(|&s| best[s].is_some())
+        .min_by_key(|&s| (Reverse(covered(s)), coins(s)))
+        .unwrap_or(0);
+    let mut counts = vec![0; values.len()];
+    counts[0] = (amount - s as u64) / largest;
+    let remainder = amount - covered(s);
+    while s > 0 {
+        let Some((_, i)) = best[s] else { break };
+        counts[i + 1] += 1;
+        s -= smaller[i] as usize;
+    }
+    (counts, remainder)
+}
+
+/// gcd
+///
+/// Purpose: Greatest common divisor.
+/// Type: `fn gcd(a: u64, b: u64) -> u64`
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// TomlValue
+///
+/// Purpose: The two kinds of TOML value a currency file may contain.
+/// Type: `enum TomlValue`
+enum TomlValue {
+    Str(String),
+    Int(i64),
+}
+
+impl TomlValue {
+    /// parse
+    ///
+    /// Purpose: Parse a basic `"string"` (with `\"` and `\\` escapes) or a
+    /// decimal integer (with optional sign and `_` separators).
+    /// Type: `fn parse(text: &str) -> Option<TomlValue>`
+    fn parse(text: &str) -> Option<TomlValue> {
+        if let Some(body) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            let mut out = String::new();
+            let mut chars = body.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => match chars.next()? {
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        _ => return None,
+                    },
+                    '"' => return None,
+                    _ => out.push(c),
+                }
+            }
+            return Some(TomlValue::Str(out));
+        }
+        let digits: String = text.chars().filter(|&c| c != '_').collect();
+        digits.parse().ok().map(TomlValue::Int)
+    }
+}
+
+/// strip_comment
+///
+/// Purpose: Drop a trailing `# comment`, ignoring `#` inside strings.
+/// Type: `fn strip_comment(line: &str) -> &str`
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELVEN: &str = r#"
+        # Currency of the elven kingdoms
+        name = "Elven leaves"
+
+        [[denomination]]
+        name = "Copper leaf"
+        code = "cl"
+        value = 1
+
+        [[denomination]]
+        name = "Silver leaf"   # most common
+        code = "sl"
+        value = 12
+
+        [[denomination]]
+        name = "Gold \"sun\" leaf"
+        code = "gl"
+        value = 144
+    "#;
+
+    #[test]
+    fn test_parse_and_breakdown() {
+        let table = CurrencyTable::from_toml_str(ELVEN).unwrap();
+        assert_eq!(table.name(), "Elven leaves");
+        let codes: Vec<&str> = table.denominations().iter().map(|d| d.code.as_str()).collect();
+        assert_eq!(codes, vec!["gl", "sl", "cl"]);
+        assert_eq!(table.denominations()[0].name, "Gold \"sun\" leaf");
+
+        let b = table.breakdown(Money::from_cp(300)); // 2×144 + 1×12 + 0
+        let counts: Vec<u64> = b.parts.iter().map(|(_, n)| *n).collect();
+        assert_eq!(counts, vec![2, 1, 0]);
+        assert_eq!(b.remainder, Money::ZERO);
+    }
+
+    #[test]
+    fn test_standard_table_matches_enum() {
+        let table = CurrencyTable::standard();
+        let b = table.breakdown(Money::from_cp(1161));
+        let counts: Vec<(&str, u64)> = b.parts.iter().map(|(d, n)| (d.code.as_str(), *n)).collect();
+        assert_eq!(counts, vec![("pp", 1), ("gp", 1), ("ep", 1), ("sp", 1), ("cp", 1)]);
+    }
+
+    #[test]
+    fn test_shipped_dwarven_table_loads() {
+        let table = CurrencyTable::load(concat!(env!("CARGO_MANIFEST_DIR"), "/currencies/dwarven.toml")).unwrap();
+        assert_eq!(table.name(), "Dwarven marks");
+        let b = table.breakdown(Money::from_cp(1161)); // 2×512 + 2×64 + 1×8 + 1
+        let counts: Vec<(&str, u64)> = b.parts.iter().map(|(d, n)| (d.code.as_str(), *n)).collect();
+        assert_eq!(counts, vec![("gc", 2), ("sm", 2), ("bm", 1), ("ib", 1)]);
+    }
+
+    fn row(code: &str, value: u64) -> CurrencyDenomination {
+        CurrencyDenomination { name: code.into(), code: code.into(), value: Money::from_cp(value) }
+    }
+
+    /// Fewest coins and remainder for `amount`, by trying every count of each value.
+    fn brute_force(values: &[u64], amount: u64) -> (u64, u64) {
+        let Some((&v, rest)) = values.split_first() else { return (amount, 0) };
+        (0..=amount / v)
+            .map(|n| {
+                let (left, coins) = brute_force(rest, amount - n * v);
+                (left, coins + n)
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_breakdown_uses_fewest_coins_for_any_table() {
+        for values in [vec![4, 3, 1], vec![100, 25, 10, 5, 1], vec![50, 20, 10, 5, 2, 1], vec![7, 5], vec![12, 9, 2]] {
+            let rows = values.iter().map(|&v| row(&v.to_string(), v)).collect();
+            let table = CurrencyTable::new("Test", rows).unwrap();
+            for amount in 0..150 {
+                let b = table.breakdown(Money::from_cp(amount));
+                let made: u64 = b.parts.iter().map(|(d, n)| d.value.cp() * n).sum();
+                let coins: u64 = b.parts.iter().map(|(_, n)| n).sum();
+                assert_eq!(made + b.remainder.cp(), amount);
+                assert_eq!((b.remainder.cp(), coins), brute_force(&values, amount), "{values:?} @ {amount}");
+            }
+        }
+        // 1/3/4: greedy would give 4 + 1 + 1.
+        let table = CurrencyTable::new("Odd", vec![row("a", 1), row("c", 3), row("d", 4)]).unwrap();
+        let counts: Vec<u64> = table.breakdown(Money::from_cp(6)).parts.iter().map(|(_, n)| *n).collect();
+        assert_eq!(counts, vec![0, 2, 0]);
+        // Far-apart values are fine when each divides the next.
+        let wide = CurrencyTable::new("Wide", vec![row("a", 1), row("b", 1_000_000_000)]).unwrap();
+        assert_eq!(wide.breakdown(Money::from_cp(2_000_000_003)).parts[0].1, 2);
+    }
+
+    #[test]
+    fn test_remainder_without_unit_coin() {
+        let rows = vec![CurrencyDenomination { name: "Bar".into(), code: "b".into(), value: Money::from_cp(5) }];
+        let table = CurrencyTable::new("Bars", rows).unwrap();
+        assert_eq!(table.breakdown(Money::from_cp(12)).remainder, Money::from_cp(2));
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let dup_value = "[[denomination]]\nname = \"A\"\ncode = \"a\"\nvalue = 5\n[[denomination]]\nname = \"B\"\ncode = \"b\"\nvalue = 5\n";
+        assert!(matches!(
+            CurrencyTable::from_toml_str(dup_value),
+            Err(CurrencyError::DuplicateValue { value }) if value == Money::from_cp(5)
+        ));
+        let dup_code = dup_value.replace("value = 5\n[", "value = 6\n[").replace("\"b\"", "\"a\"");
+        assert!(matches!(CurrencyTable::from_toml_str(&dup_code), Err(CurrencyError::DuplicateCode { .. })));
+        let negative = "[[denomination]]\nname = \"A\"\ncode = \"a\"\nvalue = -3\n";
+        assert!(matches!(CurrencyTable::from_toml_str(negative), Err(CurrencyError::NotPositive { .. })));
+        let zero = negative.replace("-3", "0");
+        assert!(matches!(CurrencyTable::from_toml_str(&zero), Err(CurrencyError::NotPositive { .. })));
+        assert!(matches!(CurrencyTable::from_toml_str("name = \"x\""), Err(CurrencyError::Empty)));
+        assert!(matches!(
+            CurrencyTable::new("Odd", vec![row("a", 3), row("b", 1_000_000_000)]),
+            Err(CurrencyError::TooFarApart { .. })
+        ));
+        assert!(matches!(
+            CurrencyTable::from_toml_str("[[denomination]]\nname = \"A\"\nvalue = 1"),
+            Err(CurrencyError::MissingField { index: 1, field: "code" })
+        ));
+        assert!(matches!(
+            CurrencyTable::from_toml_str("[[denomination]]\nvalue = \"one\""),
+            Err(CurrencyError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(CurrencyTable::load("no/such/file.toml"), Err(CurrencyError::Io(_))));
+    }
+}
@@ -0,0 +1,450 @@
+//! economy — deterministic, tick-based simulation of the coin economy
+//!
+//! Merchant NPCs each produce one kind of good and buy the others from each
+//! other, paying with real coins from their `Wallet`s (see `change.rs`).
+//! Prices rise when buyers find no stock and fall when stock piles up. At the
+//! start of every game year each mint strikes new coins to its published
+//! specs (`spec.rs`) and hands them to random merchants.
+//!
+//! Notes:
+//! - Everything random comes from one seeded `Rng`, so the same `SimConfig`
+//!   always produces the same yearly reports.
+//! - Richer merchants want more goods per tick, so new money feeds demand,
+//!   shortages and then prices: that is where inflation comes from.
+//! - Coins are only created by mints; trading moves them but never creates
+//!   or destroys them.
+//! - A year no mint strikes coins in (before 900 or after 1100 with the
+//!   standard specs) is an error rather than a year with no new money.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::change;
+use crate::money::{Money, MoneyError};
+use crate::rng::Rng;
+use crate::spec::SpecBook;
+use crate::wallet::Wallet;
+use crate::{Denomination, Mint};
+
+/// Goods traded in the simulation and their starting prices in cp.
+const GOODS: [(&str, u64); 5] = [("grain", 3), ("ale", 5), ("cloth", 25), ("iron", 40), ("spice", 120)];
+
+/// Relative chance a mint strikes each denomination (Copper..Platinum).
+const ISSUE_WEIGHTS: [u32; 5] = [40, 30, 10, 15, 5];
+
+/// A merchant wants one extra unit per tick for every this many cp held (up to two extra).
+const WEALTH_PER_EXTRA_WANT: u64 = 500;
+
+/// Unsold units per producer above which a good's price starts to fall.
+const GLUT_PER_PRODUCER: u32 = 3;
+
+/// SimConfig
+///
+/// Purpose: Everything that determines a simulation run.
+/// Type: `struct SimConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimConfig {
+    pub seed: u64,
+    pub merchants: usize,
+    pub start_year: u16,
+    pub years: u16,
+    pub ticks_per_year: u32,
+    /// Coins each mint strikes at the start of every year.
+    pub mint_output: u32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig { seed: 42, merchants: 10, start_year: 1020, years: 5, ticks_per_year: 12, mint_output: 40 }
+    }
+}
+
+/// Good
+///
+/// Purpose: A tradeable good and its current price.
+/// Type: `struct Good`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Good {
+    pub name: &'static str,
+    pub base_price: Money,
+    pub price: Money,
+}
+
+/// Merchant
+///
+/// Purpose: An NPC who produces `good`, keeps unsold units in `stock` and
+/// trades with the coins in `wallet`.
+/// Type: `struct Merchant`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merchant {
+    pub good: usize,
+    pub stock: u32,
+    pub wallet: Wallet,
+}
+
+/// DenominationSupply
+///
+/// Purpose: How many coins of one denomination are in circulation.
+/// Type: `struct DenominationSupply`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DenominationSupply {
+    pub denom: Denomination,
+    pub coins: usize,
+    pub value: Money,
+}
+
+/// YearReport
+///
+/// Purpose: The state of the economy at the end of one game year.
+/// Type: `struct YearReport`
+#[derive(Debug, Clone, PartialEq)]
+pub struct YearReport {
+    pub year: u16,
+    /// Value of the coins the mints struck this year.
+    pub minted: Money,
+    /// Average of price / starting price over all goods (1.0 = unchanged).
+    pub price_index: f64,
+    /// Change in `price_index` over the year, in percent.
+    pub inflation_pct: f64,
+    pub money_supply: Vec<DenominationSupply>,
+    pub total_money: Money,
+    /// Gini coefficient of merchant wealth: 0 = equal, 1 = one merchant has it all.
+    pub gini: f64,
+    pub poorest: Money,
+    pub median: Money,
+    pub richest: Money,
+    pub sales: u32,
+    /// Buyers who found stock but could not pay or get change.
+    pub failed_sales: u32,
+    /// Buyers who found no stock at all.
+    pub shortages: u32,
+}
+
+/// EconomyError
+///
+/// Purpose: Why a simulation year could not run. The economy is left as it
+/// was when one of these is returned.
+/// Type: `enum EconomyError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EconomyError {
+    /// No mint strikes any coin in `year`, so nothing could be issued.
+    NoCoinage { year: u16 },
+    /// The calendar would run past `u16::MAX`.
+    YearOverflow,
+    Money(MoneyError),
+}
+
+impl fmt::Display for EconomyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EconomyError::NoCoinage { year } => write!(f, "no mint strikes coins in {year}"),
+            EconomyError::YearOverflow => write!(f, "game year overflowed"),
+            EconomyError::Money(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for EconomyError {}
+
+impl From<MoneyError> for EconomyError {
+    fn from(e: MoneyError) -> Self {
+        EconomyError::Money(e)
+    }
+}
+
+/// Economy
+///
+/// Purpose: A running simulation; call `run_year` once per game year.
+/// Type: `struct Economy`
+#[derive(Debug, Clone)]
+pub struct Economy {
+    rng: Rng,
+    config: SimConfig,
+    year: u16,
+    goods: Vec<Good>,
+    merchants: Vec<Merchant>,
+    specs: SpecBook,
+    price_index: f64,
+}
+
+/// YearStats
+///
+/// Purpose: Counters collected while a year's ticks run.
+#[derive(Debug, Default)]
+struct YearStats {
+    sales: u32,
+    failed_sales: u32,
+    shortages: u32,
+}
+
+impl Economy {
+    /// new
+    ///
+    /// Purpose: Set up merchants with empty wallets and goods at base price.
+    /// Merchant `i` produces good `i % 5`.
+    /// Type: `fn new(config: SimConfig) -> Economy`
+    pub fn new(config: SimConfig) -> Economy {
+        let goods = GOODS
+            .iter()
+            .map(|&(name, cp)| Good { name, base_price: Money::from_cp(cp), price: Money::from_cp(cp) })
+            .collect();
+        let merchants = (0..config.merchants)
+            .map(|i| Merchant { good: i % GOODS.len(), stock: 0, wallet: Wallet::new() })
+            .collect();
+        Economy {
+            rng: Rng::new(config.seed),
+            config,
+            year: config.start_year,
+            goods,
+            merchants,
+            specs: SpecBook::standard(),
+            price_index: 1.0,
+        }
+    }
+
+    /// goods
+    ///
+    /// Purpose: The goods and their current prices.
+    /// Type: `fn goods(&self) -> &[Good]`
+    pub fn goods(&self) -> &[Good] {
+        &self.goods
+    }
+
+    /// run_year
+    ///
+    /// Purpose: Mint this year's coins, trade for `ticks_per_year` ticks and
+    /// report the result.
+    /// Returns: the report, or `NoCoinage` / `YearOverflow` before anything
+    /// changes.
+    /// Type: `fn run_year(&mut self) -> Result<YearReport, EconomyError>`
+    pub fn run_year(&mut self) -> Result<YearReport, EconomyError> {
+        let next_year = self.year.checked_add(1).ok_or(EconomyError::YearOverflow)?;
+        let any_coinage = Mint::ALL
+            .iter()
+            .any(|&mint| Denomination::ALL.iter().any(|&d| self.specs.find(mint, d, self.year).is_some()));
+        if self.config.mint_output > 0 && !any_coinage {
+            return Err(EconomyError::NoCoinage { year: self.year });
+        }
+
+        let minted = self.issue_coins()?;
+        let mut stats = YearStats::default();
+        for _ in 0..self.config.ticks_per_year {
+            self.tick(&mut stats)?;
+        }
+        let report = self.report(minted, &stats)?;
+        self.year = next_year;
+        Ok(report)
+    }
+
+    /// issue_coins
+    ///
+    /// Purpose: Each mint strikes `mint_output` coins dated this year and
+    /// gives each to a random merchant. A roll for a denomination the mint
+    /// doesn't strike this year produces nothing.
+    fn issue_coins(&mut self) -> Result<Money, MoneyError> {
+        let mut minted = Money::ZERO;
+        if self.merchants.is_empty() {
+            return Ok(minted);
+        }
+        for mint in Mint::ALL {
+            for _ in 0..self.config.mint_output {
+                let Some(i) = self.rng.weighted(&ISSUE_WEIGHTS) else { continue };
+                let Some(spec) = self.specs.find(mint, Denomination::ALL[i], self.year) else { continue };
+                let coin = spec.strike(self.year);
+                minted = (minted + coin.worth())?;
+                let to = self.rng.index(self.merchants.len());
+                self.merchants[to].wallet.deposit(coin);
+            }
+        }
+        Ok(minted)
+    }
+
+    /// tick
+    ///
+    /// Purpose: One round of production, trading and price changes.
+    fn tick(&mut self, stats: &mut YearStats) -> Result<(), MoneyError> {
+        for m in &mut self.merchants {
+            m.stock += 1;
+        }
+        let mut shortages = vec![0u32; self.goods.len()];
+
+        let mut order: Vec<usize> = (0..self.merchants.len()).collect();
+        self.rng.shuffle(&mut order);
+        for buyer in order {
+            let wealth = self.merchants[buyer].wallet.worth()?;
+            let wants = 1 + (wealth.cp() / WEALTH_PER_EXTRA_WANT).min(2);
+            for _ in 0..wants {
+                // Any good except the one this merchant makes.
+                let own = self.merchants[buyer].good;
+                let good = (own + 1 + self.rng.index(self.goods.len() - 1)) % self.goods.len();
+                let sellers: Vec<usize> = (0..self.merchants.len())
+                    .filter(|&i| self.merchants[i].good == good && self.merchants[i].stock > 0)
+                    .collect();
+                if sellers.is_empty() {
+                    shortages[good] += 1;
+                    stats.shortages += 1;
+                    continue;
+                }
+                let seller = sellers[self.rng.index(sellers.len())];
+                let (b, s) = pair_mut(&mut self.merchants, buyer, seller);
+                match change::purchase(&mut b.wallet, &mut s.wallet, self.goods[good].price) {
+                    Ok(_) => {
+                        s.stock -= 1;
+                        stats.sales += 1;
+                    }
+                    Err(_) => stats.failed_sales += 1,
+                }
+            }
+        }
+
+        for (g, good) in self.goods.iter_mut().enumerate() {
+            let producers = self.merchants.iter().filter(|m| m.good == g).count() as u32;
+            let stock: u32 = self.merchants.iter().filter(|m| m.good == g).map(|m| m.stock).sum();
+            let step = Money::from_cp((good.price.cp() / 20).max(1)); // 5%, at least 1 cp
+            if shortages[g] > 0 {
+                good.price = (good.price + step)?;
+            } else if stock > GLUT_PER_PRODUCER * producers {
+                good.price = (good.price - step).unwrap_or(Money::ZERO).max(Money::from_cp(1));
+            }
+        }
+        Ok(())
+    }
+
+    /// report
+    ///
+    /// Purpose: Summarise prices, money supply and wealth at year end.
+    fn report(&mut self, minted: Money, stats: &YearStats) -> Result<YearReport, MoneyError> {
+        let index = self
+            .goods
+            .iter()
+            .map(|g| g.price.cp() as f64 / g.base_price.cp() as f64)
+            .sum::<f64>()
+            / self.goods.len() as f64;
+        let inflation_pct = (index / self.price_index - 1.0) * 100.0;
+        self.price_index = index;
+
+        let money_supply = Denomination::ALL
+            .iter()
+            .map(|&denom| {
+                let coins: Vec<_> =
+                    self.merchants.iter().flat_map(|m| m.wallet.coins()).filter(|c| c.denom == denom).collect();
+                let value = coins.iter().copied().sum::<Result<Money, MoneyError>>()?;
+                Ok(DenominationSupply { denom, coins: coins.len(), value })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let total_money = money_supply.iter().map(|s| s.value).sum::<Result<Money, MoneyError>>()?;
+
+        let mut wealth = self.merchants.iter().map(|m| m.wallet.worth()).collect::<Result<Vec<_>, _>>()?;
+        wealth.sort();
+        let at = |i: usize| wealth.get(i).copied().unwrap_or(Money::ZERO);
+
+        Ok(YearReport {
+            year: self.year,
+            minted,
+            price_index: index,
+            inflation_pct,
+            money_supply,
+            total_money,
+            gini: gini(&wealth),
+            poorest: at(0),
+            median: at(wealth.len() / 2),
+            richest: at(wealth.len().saturating_sub(1)),
+            sales: stats.sales,
+            failed_sales: stats.failed_sales,
+            shortages: stats.shortages,
+        })
+    }
+}
+
+/// pair_mut
+///
+/// Purpose: Mutable references to two different merchants at once.
+fn pair_mut(merchants: &mut [Merchant], a: usize, b: usize) -> (&mut Merchant, &mut Merchant) {
+    assert_ne!(a, b, "a merchant can't trade with themselves");
+    if a < b {
+        let (left, right) = merchants.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = merchants.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+/// gini
+///
+/// Purpose: Gini coefficient of amounts sorted in ascending order.
+/// Returns: 0.0 for empty or all-zero input.
+fn gini(sorted: &[Money]) -> f64 {
+    let n = sorted.len() as f64;
+    let total: f64 = sorted.iter().map(|m| m.cp() as f64).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (2.0 * (i as f64 + 1.0) - n - 1.0) * m.cp() as f64)
+        .sum();
+    weighted / (n * total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulate(config: SimConfig) -> Result<Vec<YearReport>, EconomyError> {
+        let mut economy = Economy::new(config);
+        (0..config.years).map(|_| economy.run_year()).collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_history() {
+        let config = SimConfig { years: 3, ..SimConfig::default() };
+        assert_eq!(simulate(config), simulate(config));
+        assert_ne!(simulate(config), simulate(SimConfig { seed: 7, ..config }));
+    }
+
+    #[test]
+    fn test_money_only_comes_from_mints() {
+        let reports = simulate(SimConfig::default()).unwrap();
+        let mut minted = Money::ZERO;
+        for r in &reports {
+            minted = (minted + r.minted).unwrap();
+            assert_eq!(r.total_money, minted, "year {}", r.year);
+            let counted: usize = r.money_supply.iter().map(|s| s.coins).sum();
+            assert!(counted > 0);
+        }
+        assert!(reports.iter().map(|r| r.sales).sum::<u32>() > 0);
+    }
+
+    #[test]
+    fn test_coins_are_dated_by_year() {
+        let mut economy = Economy::new(SimConfig { start_year: 1050, ..SimConfig::default() });
+        economy.run_year().unwrap();
+        economy.run_year().unwrap();
+        let years: Vec<u16> = economy.merchants.iter().flat_map(|m| m.wallet.coins()).map(|c| c.year).collect();
+        assert!(years.iter().all(|y| (1050..=1051).contains(y)));
+        assert!(years.contains(&1050) && years.contains(&1051));
+    }
+
+    #[test]
+    fn test_years_without_coinage_or_calendar_fail() {
+        let mut economy = Economy::new(SimConfig { start_year: 1100, ..SimConfig::default() });
+        assert!(economy.run_year().is_ok());
+        assert_eq!(economy.run_year(), Err(EconomyError::NoCoinage { year: 1101 }));
+        assert_eq!(economy.year, 1101);
+
+        let idle = SimConfig { start_year: u16::MAX, mint_output: 0, ..SimConfig::default() };
+        assert_eq!(Economy::new(idle).run_year(), Err(EconomyError::YearOverflow));
+        let quiet = SimConfig { start_year: 2000, mint_output: 0, ..SimConfig::default() };
+        assert!(Economy::new(quiet).run_year().is_ok());
+    }
+
+    #[test]
+    fn test_gini() {
+        let m = Money::from_cp;
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[m(5), m(5), m(5)]), 0.0);
+        assert!((gini(&[m(0), m(0), m(0), m(100)]) - 0.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,295 @@
+//! exchange — regional currencies and money changers
+//!
+//! Every coin's face value is in Capital cp, whichever mint struck it, as
+//! everywhere else in the crate. Away from home, though, a region's coin
+//! only fetches part of its face: an `ExchangeTable` holds the money
+//! changer's buying rate for each region, and those rates can change over
+//! game days. A `MoneyChanger` takes coins from any region and pays them out
+//! in newly struck coins of another, for a fee.
+//!
+//! Notes:
+//! - Rates are parts per million of face value, so they are exact integers,
+//!   and never above `PAR`: a changer never pays more than a coin's face.
+//! - Rounding never creates money: converted amounts round down and fees
+//!   round up. Whatever is lost to rounding stays with the changer.
+//! - Coins already from the target region are handed back untouched and
+//!   carry no fee.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::money::{Money, MoneyError};
+use crate::spec::SpecBook;
+use crate::{Coin, Denomination, GameDay, Mint};
+
+/// Full face value, in rate units; also the highest rate allowed.
+pub const PAR: u64 = 1_000_000;
+
+/// ExchangeError
+///
+/// Purpose: Why an exchange could not be done.
+/// Type: `enum ExchangeError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeError {
+    /// No rate is published for `mint` on or before `day`.
+    NoRate { mint: Mint, day: GameDay },
+    /// Rates must be positive.
+    ZeroRate { mint: Mint },
+    /// Rates above `PAR` would pay out more than a coin's face value.
+    RateAbovePar { mint: Mint, ppm: u64 },
+    /// `mint` strikes no coins in `year` that can pay the amount out exactly.
+    NoCoinage { mint: Mint, year: u16 },
+    Money(MoneyError),
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::NoRate { mint, day } => write!(f, "no {mint:?} exchange rate on day {day}"),
+            ExchangeError::ZeroRate { mint } => write!(f, "{mint:?} exchange rate must be positive"),
+            ExchangeError::RateAbovePar { mint, ppm } => write!(f, "{mint:?} exchange rate {ppm} ppm is above par ({PAR} ppm)"),
+            ExchangeError::NoCoinage { mint, year } => write!(f, "the {mint:?} mint strikes no coins to pay this out in {year}"),
+            ExchangeError::Money(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for ExchangeError {}
+
+impl From<MoneyError> for ExchangeError {
+    fn from(e: MoneyError) -> Self {
+        ExchangeError::Money(e)
+    }
+}
+
+/// ExchangeTable
+///
+/// Purpose: Exchange rates per region over time. Each rate holds from the
+/// day it is set until a later rate replaces it.
+/// Type: `struct ExchangeTable`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExchangeTable {
+    rates: BTreeMap<Mint, BTreeMap<GameDay, u64>>,
+}
+
+impl ExchangeTable {
+    /// standard
+    ///
+    /// Purpose: Opening rates on day 0. Capital coin passes at par; Mountain
+    /// silver is trusted almost as much, Coastal and Desert coin less.
+    /// Type: `fn standard() -> ExchangeTable`
+    pub fn standard() -> ExchangeTable {
+        let mut table = ExchangeTable::default();
+        for (mint, ppm) in [(Mint::Capital, PAR), (Mint::Coastal, 950_000), (Mint::Mountain, 980_000), (Mint::Desert, 800_000)] {
+            table.rates.entry(mint).or_default().insert(0, ppm);
+        }
+        table
+    }
+
+    /// set_rate
+    ///
+    /// Purpose: From `day` on, a `mint` coin fetches `ppm` millionths of
+    /// its face value. Rates of 0 or above `PAR` are refused.
+    /// Type: `fn set_rate(&mut self, mint: Mint, day: GameDay, ppm: u64) -> Result<(), ExchangeError>`
+    pub fn set_rate(&mut self, mint: Mint, day: GameDay, ppm: u64) -> Result<(), ExchangeError> {
+        if ppm == 0 {
+            return Err(ExchangeError::ZeroRate { mint });
+        }
+        if ppm > PAR {
+            return Err(ExchangeError::RateAbovePar { mint, ppm });
+        }
+        self.rates.entry(mint).or_default().insert(day, ppm);
+        Ok(())
+    }
+
+    /// rate
+    ///
+    /// Purpose: The rate in force for `mint` on `day`.
+    /// Type: `fn rate(&self, mint: Mint, day: GameDay) -> Result<u64, ExchangeError>`
+    pub fn rate(&self, mint: Mint, day: GameDay) -> Result<u64, ExchangeError> {
+        self.rates
+            .get(&mint)
+            .and_then(|by_day| by_day.range(..=day).next_back())
+            .map(|(_, &ppm)| ppm)
+            .ok_or(ExchangeError::NoRate { mint, day })
+    }
+
+    /// convert
+    ///
+    /// Purpose: What `amount` of `from` coin fetches in `to` coin on `day`,
+    /// before fees: the full amount within one region, otherwise the `from`
+    /// rate applied and rounded down. Never more than `amount`.
+    /// Type: `fn convert(&self, amount: Money, from: Mint, to: Mint, day: GameDay) -> Result<Money, ExchangeError>`
+    pub fn convert(&self, amount: Money, from: Mint, to: Mint, day: GameDay) -> Result<Money, ExchangeError> {
+        if from == to {
+            return Ok(amount);
+        }
+        let fetched = u128::from(amount.cp()) * u128::from(self.rate(from, day)?) / u128::from(PAR);
+        u64::try_from(fetched).map(Money::from_cp).map_err(|_| MoneyError::Overflow.into())
+    }
+}
+
+/// Exchange
+///
+/// Purpose: The result of changing coins into another region's currency.
+/// Type: `struct Exchange`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    /// Coins handed to the customer, all from the target region.
+    pub coins: Vec<Coin>,
+    /// Face value of `coins`.
+    pub received: Money,
+    /// The changer's fee.
+    pub fee: Money,
+}
+
+/// MoneyChanger
+///
+/// Purpose: Changes coins between regions at the table's rates, charging
+/// `fee_bps` basis points (1/100 of a percent) on what is converted.
+/// Type: `struct MoneyChanger`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoneyChanger {
+    pub table: ExchangeTable,
+    pub fee_bps: u32,
+    specs: SpecBook,
+}
+
+impl MoneyChanger {
+    /// new
+    ///
+    /// Purpose: A changer using `table` and charging `fee_bps`.
+    /// Type: `fn new(table: ExchangeTable, fee_bps: u32) -> MoneyChanger`
+    pub fn new(table: ExchangeTable, fee_bps: u32) -> MoneyChanger {
+        MoneyChanger { table, fee_bps, specs: SpecBook::standard() }
+    }
+
+    /// exchange
+    ///
+    /// Purpose: Change `coins` into coins of region `to`, struck in `year`.
+    /// Foreign coins are valued at their worth and their region's rate and
+    /// converted once (rounding down), the fee is taken (rounding up), and
+    /// the rest is paid out in
+    /// the fewest `to` coins that mint strikes. Fails with `NoCoinage` rather
+    /// than keep any of the payout when the mint strikes nothing in `year`,
+    /// or nothing small enough to pay the last cp.
+    /// Type: `fn exchange(&self, coins: &[Coin], to: Mint, day: GameDay, year: u16) -> Result<Exchange, ExchangeError>`
+    pub fn exchange(&self, coins: &[Coin], to: Mint, day: GameDay, year: u16) -> Result<Exchange, ExchangeError> {
+        let (local, foreign): (Vec<Coin>, Vec<Coin>) = coins.iter().partition(|c| c.mint == to);
+        let no_coinage = ExchangeError::NoCoinage { mint: to, year };
+        if !foreign.is_empty() && Denomination::ALL.iter().all(|&d| self.specs.find(to, d, year).is_none()) {
+            return Err(no_coinage);
+        }
+
+        let mut base: u128 = 0;
+        for coin in &foreign {
+            base += u128::from(coin.worth().cp()) * u128::from(self.table.rate(coin.mint, day)?);
+        }
+        let gross = u64::try_from(base / u128::from(PAR)).map_err(|_| MoneyError::Overflow)?;
+        let fee = (u128::from(gross) * u128::from(self.fee_bps)).div_ceil(10_000);
+        let fee = u64::try_from(fee).map_err(|_| MoneyError::Overflow)?.min(gross);
+        let payout = gross - fee;
+
+        let mut out = local;
+        let mut left = payout;
+        for denom in Denomination::ALL.into_iter().rev() {
+            let Some(spec) = self.specs.find(to, denom, year) else { continue };
+            let value = u64::from(denom.value_in_cp());
+            let count = usize::try_from(left / value).map_err(|_| MoneyError::Overflow)?;
+            out.extend(std::iter::repeat_n(spec.strike(year), count));
+            left %= value;
+        }
+        if left != 0 {
+            return Err(no_coinage);
+        }
+        let received = out.iter().sum::<Result<Money, MoneyError>>()?;
+        Ok(Exchange { coins: out, received, fee: Money::from_cp(fee) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Material;
+    use crate::test_coins;
+
+    fn coin(denom: Denomination, mint: Mint) -> Coin {
+        test_coins::coin(denom, Material::Gold, mint)
+    }
+
+    #[test]
+    fn test_rates_change_over_time() {
+        let mut table = ExchangeTable::standard();
+        table.set_rate(Mint::Desert, 360, 700_000).unwrap();
+        assert_eq!(table.rate(Mint::Desert, 359), Ok(800_000));
+        assert_eq!(table.rate(Mint::Desert, 360), Ok(700_000));
+        assert_eq!(table.set_rate(Mint::Desert, 400, 0), Err(ExchangeError::ZeroRate { mint: Mint::Desert }));
+        assert_eq!(
+            table.set_rate(Mint::Mountain, 400, PAR + 1),
+            Err(ExchangeError::RateAbovePar { mint: Mint::Mountain, ppm: PAR + 1 })
+        );
+        assert_eq!(table.rate(Mint::Mountain, 400), Ok(980_000));
+
+        let empty = ExchangeTable::default();
+        assert_eq!(empty.rate(Mint::Capital, 5), Err(ExchangeError::NoRate { mint: Mint::Capital, day: 5 }));
+    }
+
+    #[test]
+    fn test_convert_rounds_down() {
+        let table = ExchangeTable::standard();
+        let m = Money::from_cp;
+        assert_eq!(table.convert(m(100), Mint::Desert, Mint::Capital, 0), Ok(m(80)));
+        // 0.98 × 99 = 97.02
+        assert_eq!(table.convert(m(99), Mint::Mountain, Mint::Coastal, 0), Ok(m(97)));
+        assert_eq!(table.convert(m(99), Mint::Mountain, Mint::Mountain, 0), Ok(m(99)));
+        // Capital coin is at par, but changing it still never gains anything.
+        for cp in [1, 7, 99, 1234, 99_999] {
+            let there = table.convert(m(cp), Mint::Capital, Mint::Mountain, 0).unwrap();
+            let back = table.convert(there, Mint::Mountain, Mint::Capital, 0).unwrap();
+            assert!(there <= m(cp) && back <= there);
+        }
+    }
+
+    #[test]
+    fn test_exchange_coins_with_fee() {
+        let changer = MoneyChanger::new(ExchangeTable::standard(), 200); // 2%
+        let purse = [
+            coin(Denomination::Platinum, Mint::Desert), // 1000 cp face, fetches 800 cp
+            coin(Denomination::Gold, Mint::Capital),    // stays as it is
+        ];
+        let ex = changer.exchange(&purse, Mint::Capital, 0, 1024).unwrap();
+        assert_eq!(ex.fee, Money::from_cp(16));
+        assert_eq!(ex.received, Money::from_cp(100 + 784));
+        assert!(ex.coins.iter().all(|c| c.mint == Mint::Capital));
+        assert!(ex.coins.contains(&purse[1]));
+        // 784 = 7 gp + 1 ep + 3 sp + 4 cp, plus the untouched gold piece
+        assert_eq!(ex.coins.len(), 1 + 7 + 1 + 3 + 4);
+    }
+
+    #[test]
+    fn test_exchange_outside_mint_years_fails() {
+        let changer = MoneyChanger::new(ExchangeTable::standard(), 200);
+        let purse = [coin(Denomination::Platinum, Mint::Capital); 2];
+        // The Mountain mint opened in 1000.
+        assert_eq!(changer.exchange(&purse, Mint::Mountain, 0, 990), Err(ExchangeError::NoCoinage { mint: Mint::Mountain, year: 990 }));
+        assert!(changer.exchange(&purse, Mint::Mountain, 0, 1000).is_ok());
+        // Coins already from the target region need no new coinage.
+        let local = [coin(Denomination::Gold, Mint::Mountain)];
+        assert_eq!(changer.exchange(&local, Mint::Mountain, 0, 990).map(|ex| ex.received), Ok(Money::from_cp(100)));
+    }
+
+    #[test]
+    fn test_exchange_never_creates_money() {
+        let changer = MoneyChanger::new(ExchangeTable::standard(), 0);
+        for from in Mint::ALL {
+            for to in Mint::ALL {
+                let purse: Vec<Coin> = Denomination::ALL.iter().map(|&d| coin(d, from)).collect();
+                let there = changer.exchange(&purse, to, 0, 1024).unwrap();
+                assert!(there.received <= Money::from_cp(1161), "{from:?} → {to:?}");
+                let back = changer.exchange(&there.coins, from, 0, 1024).unwrap();
+                assert!(back.received <= there.received, "{from:?} → {to:?} → {from:?}");
+            }
+        }
+    }
+}
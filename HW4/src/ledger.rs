@@ -0,0 +1,470 @@
+//! ledger — double-entry bookkeeping for the coin economy
+//!
+//! Every movement of money between accounts (players, merchants, the bank,
+//! mints) is a `Transaction` of debit and credit entries that must balance.
+//! A debit increases what an account holds; a credit decreases it. A mint
+//! that issues coins is credited, so its balance goes negative: it is the
+//! amount of money it has put into circulation.
+//!
+//! Notes:
+//! - Timestamps are game ticks (`Timestamp`); balances can be asked for as
+//!   of any tick.
+//! - Transactions are never edited or deleted. `reverse` posts a mirror
+//!   transaction instead, so the history stays auditable.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::money::{Balance, Money, MoneyError};
+use crate::Coin;
+
+/// Game time of a transaction, in ticks.
+pub type Timestamp = u64;
+
+/// AccountId
+///
+/// Purpose: Handle for an account opened in a `Ledger`.
+/// Type: `struct AccountId(usize)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AccountId(pub(crate) usize);
+
+/// TxId
+///
+/// Purpose: Handle for a transaction posted to a `Ledger`.
+/// Type: `struct TxId(usize)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TxId(pub(crate) usize);
+
+/// AccountKind
+///
+/// Purpose: Who owns an account.
+/// Type: `enum AccountKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    Player,
+    Merchant,
+    Bank,
+    Mint,
+}
+
+/// Account
+///
+/// Purpose: A named account in the ledger.
+/// Type: `struct Account`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub name: String,
+    pub kind: AccountKind,
+}
+
+/// Side
+///
+/// Purpose: Which side of the books an entry is on.
+/// Type: `enum Side`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+/// Entry
+///
+/// Purpose: One line of a transaction.
+/// Type: `struct Entry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub account: AccountId,
+    pub side: Side,
+    pub amount: Money,
+}
+
+/// Transaction
+///
+/// Purpose: A balanced set of entries posted at one time, with a memo. If
+/// the money moved as physical coins, they are listed in `coins`.
+/// Type: `struct Transaction`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub id: TxId,
+    pub timestamp: Timestamp,
+    pub memo: String,
+    pub entries: Vec<Entry>,
+    pub coins: Vec<Coin>,
+    /// Set on a reversal: the transaction it undoes.
+    pub reverses: Option<TxId>,
+}
+
+/// TrialBalance
+///
+/// Purpose: Sum of all debits and all credits in the ledger. In a healthy
+/// ledger they are equal.
+/// Type: `struct TrialBalance`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrialBalance {
+    pub debits: Money,
+    pub credits: Money,
+}
+
+impl TrialBalance {
+    /// is_balanced
+    ///
+    /// Purpose: True if total debits equal total credits.
+    /// Type: `fn is_balanced(&self) -> bool`
+    pub fn is_balanced(&self) -> bool {
+        self.debits == self.credits
+    }
+}
+
+/// LedgerError
+///
+/// Purpose: Why a transaction could not be posted or looked up. Nothing is
+/// posted when one of these is returned.
+/// Type: `enum LedgerError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    UnknownAccount(AccountId),
+    UnknownTransaction(TxId),
+    /// A transaction needs at least one debit and one credit.
+    NoEntries,
+    /// Entries must not be for zero.
+    ZeroAmount,
+    /// Debits and credits differ.
+    Unbalanced { debits: Money, credits: Money },
+    /// The transaction has already been reversed by `by`.
+    AlreadyReversed { tx: TxId, by: TxId },
+    /// A reversal can't be dated before the transaction it undoes.
+    ReversedBeforePosted { tx: TxId, posted: Timestamp, reversal: Timestamp },
+    Money(MoneyError),
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::UnknownAccount(id) => write!(f, "no account {}", id.0),
+            LedgerError::UnknownTransaction(id) => write!(f, "no transaction {}", id.0),
+            LedgerError::NoEntries => write!(f, "transaction needs a debit and a credit"),
+            LedgerError::ZeroAmount => write!(f, "entries must not be for zero"),
+            LedgerError::Unbalanced { debits, credits } => {
+                write!(f, "unbalanced transaction: debits {debits}, credits {credits}")
+            }
+            LedgerError::AlreadyReversed { tx, by } => {
+                write!(f, "transaction {} was already reversed by {}", tx.0, by.0)
+            }
+            LedgerError::ReversedBeforePosted { tx, posted, reversal } => {
+                write!(f, "transaction {} was posted at {posted} and can't be reversed at {reversal}", tx.0)
+            }
+            LedgerError::Money(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for LedgerError {}
+
+impl From<MoneyError> for LedgerError {
+    fn from(e: MoneyError) -> Self {
+        LedgerError::Money(e)
+    }
+}
+
+/// Ledger
+///
+/// Purpose: The books: accounts plus every transaction ever posted.
+/// Type: `struct Ledger`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ledger {
+    accounts: Vec<Account>,
+    transactions: Vec<Transaction>,
+}
+
+impl Ledger {
+    /// new
+    ///
+    /// Purpose: An empty ledger.
+    /// Type: `fn new() -> Ledger`
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    /// open_account
+    ///
+    /// Purpose: Add an account and return its id.
+    /// Type: `fn open_account(&mut self, name: &str, kind: AccountKind) -> AccountId`
+    pub fn open_account(&mut self, name: &str, kind: AccountKind) -> AccountId {
+        self.accounts.push(Account { name: name.to_string(), kind });
+        AccountId(self.accounts.len() - 1)
+    }
+
+    /// account
+    ///
+    /// Purpose: Look up an account.
+    /// Type: `fn account(&self, id: AccountId) -> Result<&Account, LedgerError>`
+    pub fn account(&self, id: AccountId) -> Result<&Account, LedgerError> {
+        self.accounts.get(id.0).ok_or(LedgerError::UnknownAccount(id))
+    }
+
+    /// transaction
+    ///
+    /// Purpose: Look up a posted transaction.
+    /// Type: `fn transaction(&self, id: TxId) -> Result<&Transaction, LedgerError>`
+    pub fn transaction(&self, id: TxId) -> Result<&Transaction, LedgerError> {
+        self.transactions.get(id.0).ok_or(LedgerError::UnknownTransaction(id))
+    }
+
+    /// post
+    ///
+    /// Purpose: Record a balanced transaction.
+    /// Parameters: `timestamp`, `memo`, `entries` (at least one debit and one
+    /// credit, none for zero, debits == credits)
+    /// Returns: the new transaction's id, or why it was rejected.
+    /// Type: `fn post(&mut self, timestamp: Timestamp, memo: &str, entries: Vec<Entry>) -> Result<TxId, LedgerError>`
+    pub fn post(&mut self, timestamp: Timestamp, memo: &str, entries: Vec<Entry>) -> Result<TxId, LedgerError> {
+        self.post_with(timestamp, memo, entries, Vec::new(), None)
+    }
+
+    /// transfer
+    ///
+    /// Purpose: Record `amount` moving from one account to another.
+    /// Type: `fn transfer(&mut self, timestamp: Timestamp, memo: &str, from: AccountId, to: AccountId, amount: Money) -> Result<TxId, LedgerError>`
+    pub fn transfer(
+        &mut self,
+        timestamp: Timestamp,
+        memo: &str,
+        from: AccountId,
+        to: AccountId,
+        amount: Money,
+    ) -> Result<TxId, LedgerError> {
+        self.post_with(timestamp, memo, transfer_entries(from, to, amount), Vec::new(), None)
+    }
+
+    /// transfer_coins
+    ///
+    /// Purpose: Record physical coins changing hands, for what they are
+    /// worth (`Coin::worth`).
+    /// Type: `fn transfer_coins(&mut self, timestamp: Timestamp, memo: &str, from: AccountId, to: AccountId, coins: &[Coin]) -> Result<TxId, LedgerError>`
+    pub fn transfer_coins(
+        &mut self,
+        timestamp: Timestamp,
+        memo: &str,
+        from: AccountId,
+        to: AccountId,
+        coins: &[Coin],
+    ) -> Result<TxId, LedgerError> {
+        let amount: Money = coins.iter().sum::<Result<Money, MoneyError>>()?;
+        self.post_with(timestamp, memo, transfer_entries(from, to, amount), coins.to_vec(), None)
+    }
+
+    /// reverse
+    ///
+    /// Purpose: Undo a transaction by posting its mirror image (every debit
+    /// becomes a credit and vice versa). A transaction can be reversed once,
+    /// at or after the time it was posted.
+    /// Type: `fn reverse(&mut self, id: TxId, timestamp: Timestamp, memo: &str) -> Result<TxId, LedgerError>`
+    pub fn reverse(&mut self, id: TxId, timestamp: Timestamp, memo: &str) -> Result<TxId, LedgerError> {
+        let original = self.transaction(id)?;
+        let entries = original
+            .entries
+            .iter()
+            .map(|e| Entry {
+                side: match e.side {
+                    Side::Debit => Side::Credit,
+                    Side::Credit => Side::Debit,
+                },
+                ..*e
+            })
+            .collect();
+        let coins = original.coins.clone();
+        self.post_with(timestamp, memo, entries, coins, Some(id))
+    }
+
+    /// balance
+    ///
+    /// Purpose: An account's current balance (debits minus credits).
+    /// Negative for accounts that have paid out more than they received,
+    /// such as mints.
+    /// Type: `fn balance(&self, account: AccountId) -> Result<Balance, LedgerError>`
+    pub fn balance(&self, account: AccountId) -> Result<Balance, LedgerError> {
+        self.balance_as_of(account, Timestamp::MAX)
+    }
+
+    /// balance_as_of
+    ///
+    /// Purpose: An account's balance counting only transactions posted at or
+    /// before `time`.
+    /// Type: `fn balance_as_of(&self, account: AccountId, time: Timestamp) -> Result<Balance, LedgerError>`
+    pub fn balance_as_of(&self, account: AccountId, time: Timestamp) -> Result<Balance, LedgerError> {
+        self.account(account)?;
+        let entries = || {
+            self.transactions
+                .iter()
+                .filter(|t| t.timestamp <= time)
+                .flat_map(|t| &t.entries)
+                .filter(|e| e.account == account)
+        };
+        let side_total = |side: Side| entries().filter(|e| e.side == side).map(|e| e.amount).sum::<Result<Money, MoneyError>>();
+        Ok(Balance::difference(side_total(Side::Debit)?, side_total(Side::Credit)?))
+    }
+
+    /// trial_balance
+    ///
+    /// Purpose: Total debits and credits over the whole ledger.
+    /// Type: `fn trial_balance(&self) -> Result<TrialBalance, MoneyError>`
+    pub fn trial_balance(&self) -> Result<TrialBalance, MoneyError> {
+        let entries = || self.transactions.iter().flat_map(|t| &t.entries);
+        let side_total = |side: Side| entries().filter(|e| e.side == side).map(|e| e.amount).sum::<Result<Money, MoneyError>>();
+        Ok(TrialBalance { debits: side_total(Side::Debit)?, credits: side_total(Side::Credit)? })
+    }
+
+    /// accounts
+    ///
+    /// Purpose: Every account with its id, in the order they were opened.
+    /// Type: `fn accounts(&self) -> impl Iterator<Item = (AccountId, &Account)>`
+    pub fn accounts(&self) -> impl Iterator<Item = (AccountId, &Account)> {
+        self.accounts.iter().enumerate().map(|(i, a)| (AccountId(i), a))
+    }
+
+    /// transactions
+    ///
+    /// Purpose: Every posted transaction, oldest first.
+    /// Type: `fn transactions(&self) -> &[Transaction]`
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// post_with
+    ///
+    /// Purpose: Validate and record a transaction with optional coins and
+    /// reversal link. Shared by every posting method and by `save.rs` when
+    /// it rebuilds a ledger, so loaded books pass the same checks.
+    pub(crate) fn post_with(
+        &mut self,
+        timestamp: Timestamp,
+        memo: &str,
+        entries: Vec<Entry>,
+        coins: Vec<Coin>,
+        reverses: Option<TxId>,
+    ) -> Result<TxId, LedgerError> {
+        for e in &entries {
+            self.account(e.account)?;
+            if e.amount == Money::ZERO {
+                return Err(LedgerError::ZeroAmount);
+            }
+        }
+        let has = |side: Side| entries.iter().any(|e| e.side == side);
+        if !has(Side::Debit) || !has(Side::Credit) {
+            return Err(LedgerError::NoEntries);
+        }
+        let side_total = |side: Side| entries.iter().filter(|e| e.side == side).map(|e| e.amount).sum::<Result<Money, MoneyError>>();
+        let debits: Money = side_total(Side::Debit)?;
+        let credits: Money = side_total(Side::Credit)?;
+        if debits != credits {
+            return Err(LedgerError::Unbalanced { debits, credits });
+        }
+
+        if let Some(original) = reverses {
+            let posted = self.transaction(original)?.timestamp;
+            if timestamp < posted {
+                return Err(LedgerError::ReversedBeforePosted { tx: original, posted, reversal: timestamp });
+            }
+            if let Some(by) = self.transactions.iter().find(|t| t.reverses == Some(original)) {
+                return Err(LedgerError::AlreadyReversed { tx: original, by: by.id });
+            }
+        }
+
+        let id = TxId(self.transactions.len());
+        self.transactions.push(Transaction { id, timestamp, memo: memo.to_string(), entries, coins, reverses });
+        Ok(id)
+    }
+}
+
+/// transfer_entries
+///
+/// Purpose: The debit/credit pair for moving `amount` from `from` to `to`.
+/// Type: `fn transfer_entries(from: AccountId, to: AccountId, amount: Money) -> Vec<Entry>`
+fn transfer_entries(from: AccountId, to: AccountId, amount: Money) -> Vec<Entry> {
+    vec![
+        Entry { account: to, side: Side::Debit, amount },
+        Entry { account: from, side: Side::Credit, amount },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_coins::coin;
+    use crate::{Denomination, Material, Mint};
+
+    fn gold() -> Coin {
+        coin(Denomination::Gold, Material::Gold, Mint::Capital)
+    }
+
+    fn setup() -> (Ledger, AccountId, AccountId, AccountId) {
+        let mut ledger = Ledger::new();
+        let mint = ledger.open_account("Capital Mint", AccountKind::Mint);
+        let player = ledger.open_account("Aria", AccountKind::Player);
+        let merchant = ledger.open_account("Borin", AccountKind::Merchant);
+        (ledger, mint, player, merchant)
+    }
+
+    #[test]
+    fn test_transfers_and_balances_over_time() {
+        let (mut ledger, mint, player, merchant) = setup();
+        ledger.transfer_coins(10, "minted", mint, player, &[gold(), gold()]).unwrap();
+        ledger.transfer(20, "sword", player, merchant, Money::from_cp(150)).unwrap();
+
+        let m = Money::from_cp;
+        assert_eq!(ledger.balance(mint), Ok(Balance::negative(m(200))));
+        assert_eq!(ledger.balance(player), Ok(Balance::positive(m(50))));
+        assert_eq!(ledger.balance(merchant), Ok(Balance::positive(m(150))));
+        assert_eq!(ledger.balance_as_of(player, 15), Ok(Balance::positive(m(200))));
+        assert_eq!(ledger.balance_as_of(player, 5), Ok(Balance::ZERO));
+        assert_eq!(ledger.balance(mint).unwrap().to_string(), "-200 cp");
+        assert_eq!(ledger.transactions()[0].coins.len(), 2);
+
+        let trial = ledger.trial_balance().unwrap();
+        assert!(trial.is_balanced());
+        assert_eq!(trial.debits, Money::from_cp(350));
+    }
+
+    #[test]
+    fn test_reversal() {
+        let (mut ledger, mint, player, _) = setup();
+        let tx = ledger.transfer(5, "bounty", mint, player, Money::from_cp(500)).unwrap();
+        assert_eq!(
+            ledger.reverse(tx, 4, "too early"),
+            Err(LedgerError::ReversedBeforePosted { tx, posted: 5, reversal: 4 })
+        );
+        let undo = ledger.reverse(tx, 6, "bounty paid twice").unwrap();
+        assert_eq!(ledger.balance(player), Ok(Balance::ZERO));
+        assert_eq!(ledger.balance_as_of(player, 5), Ok(Balance::positive(Money::from_cp(500))));
+        assert_eq!(ledger.transaction(undo).unwrap().reverses, Some(tx));
+        assert_eq!(ledger.reverse(tx, 7, "again"), Err(LedgerError::AlreadyReversed { tx, by: undo }));
+        assert!(ledger.trial_balance().unwrap().is_balanced());
+    }
+
+    #[test]
+    fn test_rejected_postings() {
+        let (mut ledger, mint, player, _) = setup();
+        let m = Money::from_cp;
+        let unbalanced = vec![
+            Entry { account: player, side: Side::Debit, amount: m(10) },
+            Entry { account: mint, side: Side::Credit, amount: m(9) },
+        ];
+        assert_eq!(ledger.post(1, "typo", unbalanced), Err(LedgerError::Unbalanced { debits: m(10), credits: m(9) }));
+        assert_eq!(
+            ledger.post(1, "one-sided", vec![Entry { account: player, side: Side::Debit, amount: m(10) }]),
+            Err(LedgerError::NoEntries)
+        );
+        assert_eq!(ledger.transfer(1, "nothing", mint, player, Money::ZERO), Err(LedgerError::ZeroAmount));
+        let ghost = AccountId(99);
+        assert_eq!(ledger.transfer(1, "ghost", mint, ghost, m(1)), Err(LedgerError::UnknownAccount(ghost)));
+        assert_eq!(ledger.reverse(TxId(7), 1, "?"), Err(LedgerError::UnknownTransaction(TxId(7))));
+        assert!(ledger.transactions().is_empty());
+
+        let split = vec![
+            Entry { account: player, side: Side::Debit, amount: m(7) },
+            Entry { account: ghost, side: Side::Debit, amount: m(3) },
+            Entry { account: mint, side: Side::Credit, amount: m(10) },
+        ];
+        assert_eq!(ledger.post(1, "split", split), Err(LedgerError::UnknownAccount(ghost)));
+    }
+}
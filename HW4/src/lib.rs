@@ -0,0 +1,290 @@
+//! HW4 — the Golden Dog RPG monetary system
+//!
+//! Coins of different denominations, materials, sizes and mints, and the
+//! money, wallets, books and markets built on them. `main.rs` demonstrates
+//! the library.
+//!
+//! Notes:
+//! - Base unit is "copper pieces" (cp). Other denominations convert to cp.
+//! - Amounts of money are `Money` values (see `money.rs`), which report
+//!   overflow instead of wrapping.
+//! - A `Wallet` (see `wallet.rs`) holds real coins for deposits and withdrawals;
+//!   `change.rs` pays prices and makes change from those limited coins.
+//! - Other currencies can be loaded from TOML (see `currency.rs` and
+//!   `currencies/dwarven.toml`).
+//! - `melt.rs` compares each coin's face value with the value of its metal.
+//! - `spec.rs` checks coins against their mint's published standards.
+//! - `ledger.rs` records money moving between accounts as double-entry books.
+//! - `economy.rs` runs a seeded merchant/mint simulation and reports
+//!   inflation, money supply and wealth distribution per year.
+//! - `loot.rs` rolls seeded coin drops per monster tier and estimates their
+//!   expected value.
+//! - `bank.rs` keeps interest-bearing deposits and amortized loans with
+//!   late fees and defaults.
+//! - `condition.rs` tracks each coin's wear and purity, and lets merchants
+//!   refuse or discount poor coins.
+//! - `catalog.rs` records mintages per issue and appraises coins for
+//!   collectors.
+//! - `save.rs` saves and loads coins, wallets and ledgers in a versioned
+//!   binary or JSON format.
+//! - `exchange.rs` changes coins between regions at buying rates
+//!   that move over game days.
+
+pub mod bank;
+pub mod catalog;
+pub mod change;
+pub mod condition;
+pub mod currency;
+pub mod economy;
+pub mod exchange;
+pub mod ledger;
+pub mod loot;
+pub mod melt;
+pub mod money;
+pub mod rng;
+pub mod save;
+pub mod spec;
+#[cfg(test)]
+mod test_coins;
+pub mod wallet;
+
+use std::fmt;
+
+use condition::Condition;
+use money::{Money, MoneyError};
+
+/// A day of game time, counted from day 0 of the campaign.
+pub type GameDay = u32;
+
+/// Denomination
+///
+/// Purpose: Represents coin denominations and their values in base units (copper pieces).
+/// Values (in cp): Copper=1, Silver=10, Electrum=50, Gold=100, Platinum=1000
+/// Type: `enum Denomination`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Denomination {
+    Copper,
+    Silver,
+    Electrum,
+    Gold,
+    Platinum,
+}
+
+impl Denomination {
+    /// Every denomination, smallest value first.
+    pub const ALL: [Denomination; 5] = [
+        Denomination::Copper,
+        Denomination::Silver,
+        Denomination::Electrum,
+        Denomination::Gold,
+        Denomination::Platinum,
+    ];
+
+    /// value_in_cp
+    ///
+    /// Purpose: Get the denomination's worth in copper pieces (cp).
+    /// Parameters: `self`
+    /// Returns: `u32` value in cp
+    /// Type: `fn value_in_cp(self) -> u32`
+    pub fn value_in_cp(self) -> u32 {
+        match self {
+            Denomination::Copper => 1,
+            Denomination::Silver => 10,
+            Denomination::Electrum => 50,
+            Denomination::Gold => 100,
+            Denomination::Platinum => 1000,
+        }
+    }
+
+    /// display_code
+    ///
+    /// Purpose: Short code string for the denomination (for concise printing).
+    /// Returns: `&'static str`
+    pub fn display_code(self) -> &'static str {
+        match self {
+            Denomination::Copper => "cp",
+            Denomination::Silver => "sp",
+            Denomination::Electrum => "ep",
+            Denomination::Gold => "gp",
+            Denomination::Platinum => "pp",
+        }
+    }
+
+    /// name
+    ///
+    /// Purpose: Lowercase name of the denomination (for verbose printing).
+    /// Returns: `&'static str`
+    pub fn name(self) -> &'static str {
+        match self {
+            Denomination::Copper => "copper",
+            Denomination::Silver => "silver",
+            Denomination::Electrum => "electrum",
+            Denomination::Gold => "gold",
+            Denomination::Platinum => "platinum",
+        }
+    }
+
+    /// from_unit
+    ///
+    /// Purpose: Look up a denomination by its display code ("gp") or its
+    /// name ("gold"), in any case.
+    /// Returns: `Option<Denomination>`
+    fn from_unit(unit: &str) -> Option<Denomination> {
+        Denomination::ALL
+            .into_iter()
+            .find(|d| unit.eq_ignore_ascii_case(d.display_code()) || unit.eq_ignore_ascii_case(d.name()))
+    }
+}
+
+/// Material
+///
+/// Purpose: Represents the coin's physical material; sets the coin's melt
+/// value through its density and price per gram (see `melt.rs`).
+/// Type: `enum Material`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Material {
+    Copper,
+    Silver,
+    Electrum,
+    Gold,
+    Platinum,
+    Mithril,
+    Obsidian,
+}
+
+impl Material {
+    /// Every material, from the common metals to the exotic ones.
+    pub const ALL: [Material; 7] = [
+        Material::Copper,
+        Material::Silver,
+        Material::Electrum,
+        Material::Gold,
+        Material::Platinum,
+        Material::Mithril,
+        Material::Obsidian,
+    ];
+}
+
+/// Mint
+///
+/// Purpose: Location where coin was minted (flavor/world-building).
+/// Type: `enum Mint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mint {
+    Capital,
+    Coastal,
+    Mountain,
+    Desert,
+}
+
+impl Mint {
+    /// Every mint of the realm.
+    pub const ALL: [Mint; 4] = [Mint::Capital, Mint::Coastal, Mint::Mountain, Mint::Desert];
+}
+
+/// Coin
+///
+/// Purpose: Represents a coin with denomination, material, size, year, and mint,
+/// plus its current condition (see `condition.rs`).
+/// Type: `struct Coin`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coin {
+    pub denom: Denomination,
+    pub material: Material,
+    pub diameter_mm: u16,
+    pub thickness_mm: u16,
+    pub year: u16,
+    pub mint: Mint,
+    pub condition: Condition,
+}
+
+impl Coin {
+    /// value_in_cp
+    ///
+    /// Purpose: Monetary value of this coin in copper pieces (cp).
+    /// Parameters: `&self`
+    /// Returns: `Money`
+    /// Type: `fn value_in_cp(&self) -> Money`
+    pub fn value_in_cp(&self) -> Money {
+        Money::from_cp(u64::from(self.denom.value_in_cp()))
+    }
+}
+
+impl fmt::Display for Coin {
+    /// Pretty print a coin as: "Gold (gp) — Gold, 25×2 mm, Year 1023, Mint: Capital, Uncirculated, 100 cp"
+    /// Debased coins also show their purity: "..., Fine, 80% pure, 50 cp"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} ({}) — {:?}, {}×{} mm, Year {}, Mint: {:?}, {:?}, ",
+            self.denom,
+            self.denom.display_code(),
+            self.material,
+            self.diameter_mm,
+            self.thickness_mm,
+            self.year,
+            self.mint,
+            self.condition.grade
+        )?;
+        if self.condition.purity_pct < 100 {
+            write!(f, "{}% pure, ", self.condition.purity_pct)?;
+        }
+        write!(f, "{}", self.value_in_cp())
+    }
+}
+
+/// total_value_in_cp
+///
+/// Purpose: Sum what all coins in an array/slice are worth in copper pieces
+/// (`Coin::worth`: debased coins count for their pure share).
+/// Parameters: `coins: &[Coin]` — array or slice of coins
+/// Returns: `Result<Money, MoneyError>` — total value, or overflow
+/// Type: `fn total_value_in_cp(coins: &[Coin]) -> Result<Money, MoneyError>`
+pub fn total_value_in_cp(coins: &[Coin]) -> Result<Money, MoneyError> {
+    coins.iter().sum()
+}
+
+/// value_breakdown
+///
+/// Purpose: Convert a total copper amount into pp/gp/sp/cp for nicer display.
+/// Parameters: `amount: Money`
+/// Returns: `(pp, gp, sp, cp)`
+/// Type: `fn value_breakdown(amount: Money) -> (u64, u64, u64, u64)`
+pub fn value_breakdown(amount: Money) -> (u64, u64, u64, u64) {
+    let mut cp = amount.cp();
+    let pp = cp / 1000;
+    cp %= 1000;
+    let gp = cp / 100;
+    cp %= 100;
+    let sp = cp / 10;
+    cp %= 10;
+    (pp, gp, sp, cp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_denom_values() {
+        assert_eq!(Denomination::Copper.value_in_cp(), 1);
+        assert_eq!(Denomination::Silver.value_in_cp(), 10);
+        assert_eq!(Denomination::Electrum.value_in_cp(), 50);
+        assert_eq!(Denomination::Gold.value_in_cp(), 100);
+        assert_eq!(Denomination::Platinum.value_in_cp(), 1000);
+    }
+
+    #[test]
+    fn test_total_and_breakdown() {
+        let purse = [
+            Coin { denom: Denomination::Gold,     material: Material::Gold,     diameter_mm: 25, thickness_mm: 2, year: 1023, mint: Mint::Capital, condition: Condition::MINT },
+            Coin { denom: Denomination::Silver,   material: Material::Silver,   diameter_mm: 22, thickness_mm: 2, year: 1023, mint: Mint::Coastal, condition: Condition::MINT },
+            Coin { denom: Denomination::Copper,   material: Material::Copper,   diameter_mm: 21, thickness_mm: 2, year: 1022, mint: Mint::Mountain, condition: Condition::MINT },
+            Coin { denom: Denomination::Platinum, material: Material::Platinum, diameter_mm: 27, thickness_mm: 2, year: 1024, mint: Mint::Capital, condition: Condition::MINT },
+            Coin { denom: Denomination::Electrum, material: Material::Electrum, diameter_mm: 24, thickness_mm: 2, year: 1021, mint: Mint::Desert, condition: Condition::MINT },
+        ];
+        let total = total_value_in_cp(&purse).unwrap(); // 100 + 10 + 1 + 1000 + 50 = 1161
+        assert_eq!(total, Money::from_cp(1161));
+        assert_eq!(value_breakdown(total), (1, 1, 6, 1)); // 1pp, 1gp, 6sp, 1cp
+    }
+}
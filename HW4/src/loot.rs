@@ -0,0 +1,304 @@
+//! loot — seeded loot tables that drop coins
+//!
+//! A `LootTable` rolls a target value within its tier's range, then fills it
+//! with coins: each coin's denomination and mint are drawn from weighted
+//! lists, it is struck to that mint's spec (`spec.rs`), and it may turn out to
+//! be a rare variant such as a Mithril gold piece.
+//!
+//! Notes:
+//! - All randomness comes from the caller's `Rng`, so a seed replays the
+//!   same drops exactly.
+//! - Only denominations no larger than what is left of the target are used.
+//!   A table without copper may fall a little short of its target, and a
+//!   drop stops at `MAX_COINS` coins; `LootDrop::shortfall` says by how much.
+//! - Drops are old hoards: coins are dated up to `max_age` years before the
+//!   current year.
+
+use std::ops::RangeInclusive;
+
+use crate::money::{Money, MoneyError};
+use crate::rng::Rng;
+use crate::spec::SpecBook;
+use crate::{Coin, Denomination, Material, Mint};
+
+/// Give up filling a drop after this many unusable rolls (a mint that did
+/// not strike the rolled coin that year).
+const MAX_REROLLS: u32 = 10_000;
+
+/// No single drop holds more coins than this.
+const MAX_COINS: usize = 10_000;
+
+/// MonsterTier
+///
+/// Purpose: How dangerous a monster is; each tier has a built-in table.
+/// Type: `enum MonsterTier`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonsterTier {
+    Vermin,
+    Brute,
+    Champion,
+    Dragon,
+}
+
+impl MonsterTier {
+    /// Every tier, weakest first.
+    pub const ALL: [MonsterTier; 4] = [MonsterTier::Vermin, MonsterTier::Brute, MonsterTier::Champion, MonsterTier::Dragon];
+}
+
+/// RareVariant
+///
+/// Purpose: A chance (per 1000 coins) that a coin of `denom` is struck in
+/// `material` instead of its usual metal.
+/// Type: `struct RareVariant`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RareVariant {
+    pub denom: Denomination,
+    pub material: Material,
+    pub per_mille: u32,
+}
+
+/// LootTable
+///
+/// Purpose: Weighted rules for the coins a monster drops.
+/// Type: `struct LootTable`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LootTable {
+    pub name: String,
+    pub value: RangeInclusive<Money>,
+    pub denominations: Vec<(Denomination, u32)>,
+    pub mints: Vec<(Mint, u32)>,
+    pub rare_variants: Vec<RareVariant>,
+    pub max_age: u16,
+}
+
+/// LootDrop
+///
+/// Purpose: The coins from one roll, and how far they fell short of the
+/// rolled target value.
+/// Type: `struct LootDrop`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LootDrop {
+    pub coins: Vec<Coin>,
+    pub shortfall: Money,
+}
+
+/// LootAnalysis
+///
+/// Purpose: Statistics from rolling a table many times.
+/// Type: `struct LootAnalysis`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LootAnalysis {
+    pub samples: u32,
+    /// Average face value per drop, in cp.
+    pub expected_cp: f64,
+    /// Average melt value per drop, in cp.
+    pub expected_melt_cp: f64,
+    pub expected_coins: f64,
+    /// Share of dropped coins that were rare variants.
+    pub rare_rate: f64,
+    pub min: Money,
+    pub max: Money,
+}
+
+impl LootTable {
+    /// for_tier
+    ///
+    /// Purpose: The built-in table for a monster tier.
+    /// Type: `fn for_tier(tier: MonsterTier) -> LootTable`
+    pub fn for_tier(tier: MonsterTier) -> LootTable {
+        use Denomination as D;
+        let cp = Money::from_cp;
+        let even_mints = vec![(Mint::Capital, 1), (Mint::Coastal, 1), (Mint::Mountain, 1), (Mint::Desert, 1)];
+        let mithril_gold = |per_mille| RareVariant { denom: D::Gold, material: Material::Mithril, per_mille };
+        let (name, value, denominations, mints, rare_variants) = match tier {
+            MonsterTier::Vermin => ("Vermin", cp(1)..=cp(30), vec![(D::Copper, 70), (D::Silver, 30)], even_mints, vec![]),
+            MonsterTier::Brute => (
+                "Brute",
+                cp(20)..=cp(300),
+                vec![(D::Copper, 30), (D::Silver, 50), (D::Electrum, 10), (D::Gold, 10)],
+                even_mints,
+                vec![mithril_gold(10)],
+            ),
+            MonsterTier::Champion => (
+                "Champion",
+                cp(200)..=cp(2000),
+                vec![(D::Silver, 20), (D::Electrum, 20), (D::Gold, 50), (D::Platinum, 10)],
+                vec![(Mint::Capital, 3), (Mint::Coastal, 2), (Mint::Mountain, 2), (Mint::Desert, 1)],
+                vec![mithril_gold(20)],
+            ),
+            MonsterTier::Dragon => (
+                "Dragon",
+                cp(2000)..=cp(20_000),
+                vec![(D::Gold, 60), (D::Platinum, 40)],
+                vec![(Mint::Capital, 1), (Mint::Mountain, 4)],
+                vec![
+                    mithril_gold(50),
+                    RareVariant { denom: D::Platinum, material: Material::Mithril, per_mille: 20 },
+                ],
+            ),
+        };
+        LootTable { name: name.to_string(), value, denominations, mints, rare_variants, max_age: 50 }
+    }
+
+    /// roll
+    ///
+    /// Purpose: Generate one drop of coins.
+    /// Parameters: `rng` — the seeded generator; `year` — the current game
+    /// year; `specs` — mint standards used to strike the coins
+    /// Returns: the coins (possibly empty if the table can't strike anything)
+    /// and the part of the target they could not cover.
+    /// Type: `fn roll(&self, rng: &mut Rng, year: u16, specs: &SpecBook) -> LootDrop`
+    pub fn roll(&self, rng: &mut Rng, year: u16, specs: &SpecBook) -> LootDrop {
+        let (low, high) = (self.value.start().cp(), self.value.end().cp());
+        // A range covering every u64 has no `below` bound; any value will do.
+        let offset = match high.saturating_sub(low).checked_add(1) {
+            Some(span) => rng.below(span),
+            None => rng.next_u64(),
+        };
+        let target = low + offset;
+        let mint_weights: Vec<u32> = self.mints.iter().map(|m| m.1).collect();
+
+        let mut remaining = target;
+        let mut coins = Vec::new();
+        let mut rerolls = 0;
+        while coins.len() < MAX_COINS && rerolls < MAX_REROLLS {
+            let weights: Vec<u32> = self
+                .denominations
+                .iter()
+                .map(|&(d, w)| if u64::from(d.value_in_cp()) <= remaining { w } else { 0 })
+                .collect();
+            let Some(d) = rng.weighted(&weights) else { break };
+            let Some(m) = rng.weighted(&mint_weights) else { break };
+            let denom = self.denominations[d].0;
+            let coin_year = year.saturating_sub(rng.below(u64::from(self.max_age) + 1) as u16);
+            // Mints that did not strike this coin that year make us roll again.
+            let Some(spec) = specs.find(self.mints[m].0, denom, coin_year) else {
+                rerolls += 1;
+                continue;
+            };
+
+            let mut coin = spec.strike(coin_year);
+            for variant in self.rare_variants.iter().filter(|v| v.denom == denom) {
+                if rng.below(1000) < u64::from(variant.per_mille) {
+                    coin.material = variant.material;
+                    break;
+                }
+            }
+            remaining -= u64::from(denom.value_in_cp());
+            coins.push(coin);
+        }
+        LootDrop { coins, shortfall: Money::from_cp(remaining) }
+    }
+
+    /// analyze
+    ///
+    /// Purpose: Roll the table `samples` times (from `seed`) and report the
+    /// expected value in cp and other statistics.
+    /// Type: `fn analyze(&self, seed: u64, samples: u32, year: u16, specs: &SpecBook) -> Result<LootAnalysis, MoneyError>`
+    pub fn analyze(&self, seed: u64, samples: u32, year: u16, specs: &SpecBook) -> Result<LootAnalysis, MoneyError> {
+        let mut rng = Rng::new(seed);
+        let (mut total, mut melt, mut coins, mut rare) = (0u128, 0u128, 0u64, 0u64);
+        let (mut min, mut max) = (None::<Money>, Money::ZERO);
+        for _ in 0..samples {
+            let drop = self.roll(&mut rng, year, specs).coins;
+            let value: Money = drop.iter().sum::<Result<Money, MoneyError>>()?;
+            total += u128::from(value.cp());
+            melt += drop.iter().map(|c| u128::from(c.melt_value().cp())).sum::<u128>();
+            coins += drop.len() as u64;
+            rare += drop.iter().filter(|c| self.is_rare(c)).count() as u64;
+            min = Some(min.map_or(value, |m| m.min(value)));
+            max = max.max(value);
+        }
+        let n = f64::from(samples.max(1));
+        Ok(LootAnalysis {
+            samples,
+            expected_cp: total as f64 / n,
+            expected_melt_cp: melt as f64 / n,
+            expected_coins: coins as f64 / n,
+            rare_rate: if coins == 0 { 0.0 } else { rare as f64 / coins as f64 },
+            min: min.unwrap_or(Money::ZERO),
+            max,
+        })
+    }
+
+    /// is_rare
+    ///
+    /// Purpose: True if the coin matches one of this table's rare variants.
+    fn is_rare(&self, coin: &Coin) -> bool {
+        self.rare_variants.iter().any(|v| v.denom == coin.denom && v.material == coin.material)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_drops() {
+        let specs = SpecBook::standard();
+        let table = LootTable::for_tier(MonsterTier::Brute);
+        let (mut a, mut b) = (Rng::new(99), Rng::new(99));
+        for _ in 0..20 {
+            assert_eq!(table.roll(&mut a, 1024, &specs), table.roll(&mut b, 1024, &specs));
+        }
+    }
+
+    #[test]
+    fn test_drops_stay_in_range_and_use_allowed_coins() {
+        let specs = SpecBook::standard();
+        let mut rng = Rng::new(5);
+        for tier in MonsterTier::ALL {
+            let table = LootTable::for_tier(tier);
+            for _ in 0..200 {
+                let drop = table.roll(&mut rng, 1024, &specs).coins;
+                let value: Money = drop.iter().sum::<Result<Money, MoneyError>>().unwrap();
+                assert!(value <= *table.value.end(), "{tier:?}: {value}");
+                for coin in &drop {
+                    assert!(table.denominations.iter().any(|&(d, w)| d == coin.denom && w > 0));
+                    assert!(table.mints.iter().any(|&(m, _)| m == coin.mint));
+                    assert!((974..=1024).contains(&coin.year));
+                    assert!(table.is_rare(coin) || specs.is_genuine(coin), "{coin}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_copper_tables_hit_their_target() {
+        let specs = SpecBook::standard();
+        let table = LootTable { value: Money::from_cp(57)..=Money::from_cp(57), ..LootTable::for_tier(MonsterTier::Brute) };
+        let drop = table.roll(&mut Rng::new(3), 1024, &specs);
+        assert_eq!(drop.coins.iter().sum::<Result<Money, MoneyError>>(), Ok(Money::from_cp(57)));
+        assert_eq!(drop.shortfall, Money::ZERO);
+    }
+
+    #[test]
+    fn test_rare_variants_and_analysis() {
+        let specs = SpecBook::standard();
+        let mut table = LootTable::for_tier(MonsterTier::Dragon);
+        table.rare_variants = vec![RareVariant { denom: Denomination::Gold, material: Material::Mithril, per_mille: 1000 }];
+        let drop = table.roll(&mut Rng::new(1), 1024, &specs).coins;
+        assert!(drop.iter().filter(|c| c.denom == Denomination::Gold).all(|c| c.material == Material::Mithril));
+
+        let vermin = LootTable::for_tier(MonsterTier::Vermin);
+        let report = vermin.analyze(7, 2000, 1024, &specs).unwrap();
+        assert_eq!(report, vermin.analyze(7, 2000, 1024, &specs).unwrap());
+        assert!((report.expected_cp - 15.5).abs() < 1.0, "{report:?}");
+        assert!(report.min >= Money::from_cp(1) && report.max <= Money::from_cp(30));
+        assert_eq!(report.rare_rate, 0.0);
+    }
+
+    #[test]
+    fn test_huge_targets_stop_and_report_shortfall() {
+        let specs = SpecBook::standard();
+        let table = LootTable { value: Money::ZERO..=Money::from_cp(u64::MAX), ..LootTable::for_tier(MonsterTier::Vermin) };
+        let drop = table.roll(&mut Rng::new(8), 1024, &specs);
+        assert_eq!(drop.coins.len(), MAX_COINS);
+        assert!(drop.shortfall > Money::ZERO);
+
+        // A table whose mints strike nothing gives up after the rerolls, short by the whole target.
+        let closed = LootTable { value: Money::from_cp(50)..=Money::from_cp(50), ..LootTable::for_tier(MonsterTier::Vermin) };
+        let drop = closed.roll(&mut Rng::new(8), 500, &specs);
+        assert_eq!(drop, LootDrop { coins: vec![], shortfall: Money::from_cp(50) });
+    }
+}
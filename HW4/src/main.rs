@@ -3,204 +3,109 @@
 //! Scenario: You work for Golden Dog Game Company. Design an in-game monetary
 //! system with coins of different denominations, materials, and sizes. This
 //! demo creates several coins, stores them in an array, and prints values and
-//! totals.
+//! totals, then takes one short look at each system built on them.
 //!
 //! Notes:
-//! - Base unit is "copper pieces" (cp). Other denominations convert to cp.
+//! - The coin types and everything built on them live in the library
+//!   (`lib.rs`), where each module's tests cover it in detail; this file
+//!   only shows them off.
 //! - We show how to use arrays (`[T; N]`) and slices (`&[T]`).
 
-use std::fmt;
-
-/// Denomination
-///
-/// Purpose: Represents coin denominations and their values in base units (copper pieces).
-/// Values (in cp): Copper=1, Silver=10, Electrum=50, Gold=100, Platinum=1000
-/// Type: `enum Denomination`
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Denomination {
-    Copper,
-    Silver,
-    Electrum,
-    Gold,
-    Platinum,
-}
-
-impl Denomination {
-    /// value_in_cp
-    ///
-    /// Purpose: Get the denomination's worth in copper pieces (cp).
-    /// Parameters: `self`
-    /// Returns: `u32` value in cp
-    /// Type: `fn value_in_cp(self) -> u32`
-    fn value_in_cp(self) -> u32 {
-        match self {
-            Denomination::Copper => 1,
-            Denomination::Silver => 10,
-            Denomination::Electrum => 50,
-            Denomination::Gold => 100,
-            Denomination::Platinum => 1000,
-        }
-    }
-
-    /// display_code
-    ///
-    /// Purpose: Short code string for the denomination (for concise printing).
-    /// Returns: `&'static str`
-    fn display_code(self) -> &'static str {
-        match self {
-            Denomination::Copper => "cp",
-            Denomination::Silver => "sp",
-            Denomination::Electrum => "ep",
-            Denomination::Gold => "gp",
-            Denomination::Platinum => "pp",
-        }
-    }
-}
-
-/// Material
-///
-/// Purpose: Represents the coin's physical material; impacts flavor only here.
-/// Type: `enum Material`
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Material {
-    Copper,
-    Silver,
-    Electrum,
-    Gold,
-    Platinum,
-    Mithril,
-    Obsidian,
-}
-
-/// Mint
-///
-/// Purpose: Location where coin was minted (flavor/world-building).
-/// Type: `enum Mint`
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Mint {
-    Capital,
-    Coastal,
-    Mountain,
-    Desert,
-}
-
-/// Coin
-///
-/// Purpose: Represents a coin with denomination, material, size, year, and mint.
-/// Type: `struct Coin`
-#[derive(Debug, Clone, Copy, PartialEq)]
-struct Coin {
-    denom: Denomination,
-    material: Material,
-    diameter_mm: u16,
-    thickness_mm: u16,
-    year: u16,
-    mint: Mint,
-}
-
-impl Coin {
-    /// value_in_cp
-    ///
-    /// Purpose: Monetary value of this coin in copper pieces (cp).
-    /// Parameters: `&self`
-    /// Returns: `u32`
-    /// Type: `fn value_in_cp(&self) -> u32`
-    fn value_in_cp(&self) -> u32 {
-        self.denom.value_in_cp()
-    }
-}
-
-impl fmt::Display for Coin {
-    /// Pretty print a coin as: "Gold (gp), Gold, 25×2 mm, Year 1023, Mint: Capital, 100 cp"
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{:?} ({}) — {:?}, {}×{} mm, Year {}, Mint: {:?}, {} cp",
-            self.denom,
-            self.denom.display_code(),
-            self.material,
-            self.diameter_mm,
-            self.thickness_mm,
-            self.year,
-            self.mint,
-            self.value_in_cp()
-        )
-    }
-}
-
-/// total_value_in_cp
-///
-/// Purpose: Sum the values of all coins in an array/slice in copper pieces.
-/// Parameters: `coins: &[Coin]` — array or slice of coins
-/// Returns: `u32` — total value in cp
-/// Type: `fn total_value_in_cp(coins: &[Coin]) -> u32`
-fn total_value_in_cp(coins: &[Coin]) -> u32 {
-    coins.iter().map(|c| c.value_in_cp()).sum()
-}
-
-/// value_breakdown
-///
-/// Purpose: Convert a total copper amount into pp/gp/sp/cp for nicer display.
-/// Parameters: `cp: u32`
-/// Returns: `(pp, gp, sp, cp)`
-/// Type: `fn value_breakdown(cp: u32) -> (u32, u32, u32, u32)`
-fn value_breakdown(mut cp: u32) -> (u32, u32, u32, u32) {
-    let pp = cp / 1000;
-    cp %= 1000;
-    let gp = cp / 100;
-    cp %= 100;
-    let sp = cp / 10;
-    cp %= 10;
-    (pp, gp, sp, cp)
-}
-
-fn main() {
+use std::error::Error;
+
+use hw4::bank::{Bank, BankTerms};
+use hw4::catalog::{Catalog, Issue};
+use hw4::change;
+use hw4::condition::{CoinPolicy, Condition, Grade};
+use hw4::currency::CurrencyTable;
+use hw4::economy::{Economy, SimConfig};
+use hw4::exchange::{ExchangeTable, MoneyChanger};
+use hw4::ledger::{AccountKind, Ledger};
+use hw4::loot::{LootTable, MonsterTier};
+use hw4::money::{Money, MoneyStyle};
+use hw4::rng::Rng;
+use hw4::save;
+use hw4::spec::SpecBook;
+use hw4::wallet::Wallet;
+use hw4::{total_value_in_cp, value_breakdown, Coin, Denomination, Material, Mint};
+
+fn main() -> Result<(), Box<dyn Error>> {
     // --- Create an array of coins (fixed-size array demonstrates "arrays") ---
-    // Each coin: denomination, material, size, year, mint.
-    let wallet: [Coin; 6] = [
-        Coin { denom: Denomination::Gold,     material: Material::Gold,     diameter_mm: 25, thickness_mm: 2, year: 1023, mint: Mint::Capital },
-        Coin { denom: Denomination::Silver,   material: Material::Silver,   diameter_mm: 22, thickness_mm: 2, year: 1023, mint: Mint::Coastal },
-        Coin { denom: Denomination::Copper,   material: Material::Copper,   diameter_mm: 21, thickness_mm: 2, year: 1022, mint: Mint::Mountain },
-        Coin { denom: Denomination::Platinum, material: Material::Platinum, diameter_mm: 27, thickness_mm: 2, year: 1024, mint: Mint::Capital },
-        Coin { denom: Denomination::Electrum, material: Material::Electrum, diameter_mm: 24, thickness_mm: 2, year: 1021, mint: Mint::Desert },
-        Coin { denom: Denomination::Gold,     material: Material::Mithril,  diameter_mm: 25, thickness_mm: 2, year: 1020, mint: Mint::Mountain },
+    // Each coin: denomination, material, size, year, mint, condition.
+    let coins: [Coin; 6] = [
+        Coin { denom: Denomination::Gold,     material: Material::Gold,     diameter_mm: 25, thickness_mm: 2, year: 1023, mint: Mint::Capital,  condition: Condition::MINT },
+        Coin { denom: Denomination::Silver,   material: Material::Silver,   diameter_mm: 22, thickness_mm: 2, year: 1023, mint: Mint::Coastal,  condition: Condition::MINT },
+        Coin { denom: Denomination::Copper,   material: Material::Copper,   diameter_mm: 21, thickness_mm: 2, year: 1022, mint: Mint::Mountain, condition: Condition::MINT },
+        Coin { denom: Denomination::Platinum, material: Material::Platinum, diameter_mm: 27, thickness_mm: 2, year: 1024, mint: Mint::Capital,  condition: Condition::MINT },
+        Coin { denom: Denomination::Electrum, material: Material::Electrum, diameter_mm: 24, thickness_mm: 2, year: 1021, mint: Mint::Desert,   condition: Condition::MINT },
+        Coin { denom: Denomination::Gold,     material: Material::Mithril,  diameter_mm: 25, thickness_mm: 2, year: 1020, mint: Mint::Mountain, condition: Condition::MINT },
     ];
 
     println!("--- Golden Dog RPG Coin Demonstration ---\n");
-    for (i, coin) in wallet.iter().enumerate() {
+    for (i, coin) in coins.iter().enumerate() {
         println!("Coin #{i}: {coin}");
     }
 
-    let total_cp = total_value_in_cp(&wallet);
-    let (pp, gp, sp, cp) = value_breakdown(total_cp);
+    let total = total_value_in_cp(&coins)?;
+    let (pp, gp, sp, cp) = value_breakdown(total);
+    println!("\nTotal value: {total}  →  {pp} pp, {gp} gp, {sp} sp, {cp} cp ({})", total.styled(MoneyStyle::Verbose));
+
+    // --- One look at each system built on the coins ---
+    let specs = SpecBook::standard();
+    let genuine = coins.iter().filter(|c| specs.is_genuine(c)).count();
+    println!("Mint standards: {genuine} of {} coins are genuine", coins.len());
+
+    let dwarven = CurrencyTable::load(concat!(env!("CARGO_MANIFEST_DIR"), "/currencies/dwarven.toml"))?;
+    let parts: Vec<String> = dwarven.breakdown(total).parts.iter().map(|(d, n)| format!("{n} {}", d.code)).collect();
+    println!("In {}: {}", dwarven.name(), parts.join(", "));
+
+    let mut wallet: Wallet = coins.into_iter().collect();
+    let mut merchant: Wallet = [Coin { denom: Denomination::Silver, material: Material::Silver, ..coins[0] }; 2].into_iter().collect();
+    let deal = change::purchase(&mut wallet, &mut merchant, Money::from_cp(80))?;
+    println!("Bought for 80 cp: paid {} coin(s), got {} back, {} left", deal.paid.len(), deal.change.len(), wallet.worth()?);
+
+    let debased = Coin { condition: Condition { grade: Grade::Fine, uses: 80, ..Condition::debased(80) }, ..coins[4] };
+    let picky = CoinPolicy { refuse_below: Grade::Fine, discount_below: Grade::VeryFine, discount_pct: 10, min_purity_pct: 70 };
+    println!("Debased: {debased} (worth {}); a picky merchant: {}", debased.worth(), picky.assess(&debased)?);
+
+    let mut books = Ledger::new();
+    let mint = books.open_account("Capital Mint", AccountKind::Mint);
+    let aria = books.open_account("Aria", AccountKind::Player);
+    books.transfer_coins(1, "new issue", mint, aria, &coins)?;
+    println!("Ledger: mint {}, Aria {}", books.balance(mint)?, books.balance(aria)?);
+
+    let config = SimConfig::default();
+    let mut economy = Economy::new(config);
+    for _ in 0..config.years {
+        let r = economy.run_year()?;
+        println!("Economy {}: minted {}, prices ×{:.2} ({:+.1}%), gini {:.2}", r.year, r.minted, r.price_index, r.inflation_pct, r.gini);
+    }
+
+    let hoard = LootTable::for_tier(MonsterTier::Dragon).roll(&mut Rng::new(2024), 1024, &specs).coins;
+    println!("Dragon hoard: {} coins worth {}", hoard.len(), total_value_in_cp(&hoard)?);
 
-    println!("\nTotal value: {total_cp} cp  →  {pp} pp, {gp} gp, {sp} sp, {cp} cp");
-}
+    let catalog = Catalog::standard(&specs);
+    let quest = catalog.completeness(&hoard, |i: &Issue| i.mint == Mint::Mountain && i.material == Material::Mithril);
+    let still_missing = quest.missing.first().map_or("nothing".to_string(), Issue::to_string);
+    println!("Mountain Mithril set: {}/{} issues, e.g. still missing {still_missing}", quest.owned, quest.total);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_denom_values() {
-        assert_eq!(Denomination::Copper.value_in_cp(), 1);
-        assert_eq!(Denomination::Silver.value_in_cp(), 10);
-        assert_eq!(Denomination::Electrum.value_in_cp(), 50);
-        assert_eq!(Denomination::Gold.value_in_cp(), 100);
-        assert_eq!(Denomination::Platinum.value_in_cp(), 1000);
+    let mut table = ExchangeTable::standard();
+    table.set_rate(Mint::Mountain, 360, 900_000)?;
+    let changer = MoneyChanger::new(table, 300);
+    for day in [0, 360] {
+        let ex = changer.exchange(&hoard, Mint::Capital, day, 1024)?;
+        println!("Hoard into Capital coin on day {day}: {} after a {} fee", ex.received, ex.fee);
     }
 
-    #[test]
-    fn test_total_and_breakdown() {
-        let purse = [
-            Coin { denom: Denomination::Gold,     material: Material::Gold,     diameter_mm: 25, thickness_mm: 2, year: 1023, mint: Mint::Capital },
-            Coin { denom: Denomination::Silver,   material: Material::Silver,   diameter_mm: 22, thickness_mm: 2, year: 1023, mint: Mint::Coastal },
-            Coin { denom: Denomination::Copper,   material: Material::Copper,   diameter_mm: 21, thickness_mm: 2, year: 1022, mint: Mint::Mountain },
-            Coin { denom: Denomination::Platinum, material: Material::Platinum, diameter_mm: 27, thickness_mm: 2, year: 1024, mint: Mint::Capital },
-            Coin { denom: Denomination::Electrum, material: Material::Electrum, diameter_mm: 24, thickness_mm: 2, year: 1021, mint: Mint::Desert },
-        ];
-        let total_cp = total_value_in_cp(&purse); // 100 + 10 + 1 + 1000 + 50 = 1161
-        assert_eq!(total_cp, 1161);
-        assert_eq!(value_breakdown(total_cp), (1, 1, 6, 1)); // 1pp, 1gp, 6sp, 1cp
+    let mut bank = Bank::new(BankTerms::default(), 0);
+    let loan = bank.issue_loan("Bram", Money::from_cp(5000), 20_000, 6, 30)?;
+    for day in [60, 160] {
+        bank.advance_to(day)?;
+        println!("Bank day {day}: loan {}", bank.loan_status(loan)?);
     }
+
+    let bytes = save::to_binary(&coins)?;
+    let ledger_json = save::ledger_to_json(&books);
+    println!("Saved (v{}): coins in {} bytes, ledger in {} bytes of JSON", save::FORMAT_VERSION, bytes.len(), ledger_json.len());
+    Ok(())
 }
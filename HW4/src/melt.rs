@@ -0,0 +1,166 @@
+//! melt — intrinsic metal value of coins
+//!
+//! A coin is modelled as a cylinder of `diameter_mm` × `thickness_mm`. Its
+//! mass is that volume times the `Material`'s density, and its melt value is
+//! the mass times the material's price per gram. Comparing melt value with
+//! face value shows which coins are worth more as metal (and so get hoarded
+//! or melted down).
+//!
+//! Notes:
+//! - Prices are in copper pieces per gram and may be fractional; melt values
+//!   round down to whole cp, since a smelter never pays out fractions.
+//! - Mithril is light but very valuable, so a Mithril-struck gold piece melts
+//!   for more than its face value.
+
+use std::f64::consts::PI;
+
+use crate::money::Money;
+use crate::{Coin, Material};
+
+impl Material {
+    /// density_g_per_cm3
+    ///
+    /// Purpose: Density of the material in grams per cubic centimetre.
+    /// Type: `fn density_g_per_cm3(self) -> f64`
+    pub fn density_g_per_cm3(self) -> f64 {
+        match self {
+            Material::Copper => 8.96,
+            Material::Silver => 10.49,
+            Material::Electrum => 15.0,
+            Material::Gold => 19.32,
+            Material::Platinum => 21.45,
+            Material::Mithril => 4.5,
+            Material::Obsidian => 2.4,
+        }
+    }
+
+    /// price_cp_per_gram
+    ///
+    /// Purpose: What a smelter pays for one gram of the material, in cp.
+    /// Type: `fn price_cp_per_gram(self) -> f64`
+    pub fn price_cp_per_gram(self) -> f64 {
+        match self {
+            Material::Copper => 0.1,
+            Material::Silver => 1.0,
+            Material::Electrum => 3.0,
+            Material::Gold => 5.0,
+            Material::Platinum => 30.0,
+            Material::Mithril => 50.0,
+            Material::Obsidian => 0.05,
+        }
+    }
+}
+
+impl Coin {
+    /// volume_cm3
+    ///
+    /// Purpose: Volume of the coin as a cylinder, in cubic centimetres.
+    /// Type: `fn volume_cm3(&self) -> f64`
+    pub fn volume_cm3(&self) -> f64 {
+        let radius = f64::from(self.diameter_mm) / 2.0;
+        PI * radius * radius * f64::from(self.thickness_mm) / 1000.0
+    }
+
+    /// mass_g
+    ///
+    /// Purpose: Mass of the coin in grams, less what wear has rubbed off.
+    /// Type: `fn mass_g(&self) -> f64`
+    pub fn mass_g(&self) -> f64 {
+        let left = f64::from(self.condition.grade.metal_percent()) / 100.0;
+        self.volume_cm3() * self.material.density_g_per_cm3() * left
+    }
+
+    /// melt_value
+    ///
+    /// Purpose: What the coin's metal is worth, rounded down to whole cp.
+    /// Only the pure share of a debased coin counts; the filler is worthless.
+    /// Type: `fn melt_value(&self) -> Money`
+    pub fn melt_value(&self) -> Money {
+        let purity = f64::from(self.condition.purity_pct) / 100.0;
+        let cp = self.mass_g() * self.material.price_cp_per_gram() * purity;
+        // `as` saturates, and the inputs are bounded by u16 sizes anyway.
+        Money::from_cp(cp.floor() as u64)
+    }
+}
+
+/// MeltLine
+///
+/// Purpose: One row of a melt report: a coin with its face and melt values.
+/// Type: `struct MeltLine`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeltLine {
+    pub coin: Coin,
+    pub mass_g: f64,
+    pub face: Money,
+    pub melt: Money,
+}
+
+impl MeltLine {
+    /// worth_melting
+    ///
+    /// Purpose: True if the metal is worth more than the coin's face value.
+    /// Type: `fn worth_melting(&self) -> bool`
+    pub fn worth_melting(&self) -> bool {
+        self.melt > self.face
+    }
+
+    /// melt_ratio
+    ///
+    /// Purpose: Melt value as a fraction of face value (1.0 = equal).
+    /// Type: `fn melt_ratio(&self) -> f64`
+    pub fn melt_ratio(&self) -> f64 {
+        self.melt.cp() as f64 / self.face.cp() as f64
+    }
+}
+
+/// melt_report
+///
+/// Purpose: Compare face value with melt value for every coin.
+/// Parameters: `coins: &[Coin]`
+/// Returns: `Vec<MeltLine>` — one row per coin, in the same order.
+/// Type: `fn melt_report(coins: &[Coin]) -> Vec<MeltLine>`
+pub fn melt_report(coins: &[Coin]) -> Vec<MeltLine> {
+    coins
+        .iter()
+        .map(|&coin| MeltLine { coin, mass_g: coin.mass_g(), face: coin.value_in_cp(), melt: coin.melt_value() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_coins;
+    use crate::{Denomination, Mint};
+
+    fn coin(denom: Denomination, material: Material) -> Coin {
+        test_coins::coin(denom, material, Mint::Capital)
+    }
+
+    #[test]
+    fn test_volume_and_mass() {
+        let gold = coin(Denomination::Gold, Material::Gold);
+        // π × 12.5² × 2 mm³ = 981.75 mm³
+        assert!((gold.volume_cm3() - 0.98175).abs() < 1e-4);
+        assert!((gold.mass_g() - 0.98175 * 19.32).abs() < 1e-3);
+        // 18.97 g × 5 cp/g = 94.8 cp, rounded down
+        assert_eq!(gold.melt_value(), Money::from_cp(94));
+    }
+
+    #[test]
+    fn test_report_flags_mithril_gold() {
+        let coins = [coin(Denomination::Gold, Material::Gold), coin(Denomination::Gold, Material::Mithril)];
+        let report = melt_report(&coins);
+        assert!(!report[0].worth_melting());
+        assert!(report[1].worth_melting());
+        assert_eq!(report[1].melt, Money::from_cp(220)); // 4.42 g × 50 cp/g
+        assert!(report[1].melt_ratio() > 2.0);
+    }
+
+    #[test]
+    fn test_every_material_has_positive_density_and_price() {
+        for m in Material::ALL {
+            assert!(m.density_g_per_cm3() > 0.0, "{m:?}");
+            assert!(m.price_cp_per_gram() > 0.0, "{m:?}");
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! money — overflow-safe amounts of copper pieces
+//!
+//! `Money` wraps a `u64` count of copper pieces (cp). Arithmetic never wraps
+//! or panics: `+`, `-` and `* u32` return `Result<Money, MoneyError>`, so a
+//! large treasury reports overflow and an over-spend reports how much is
+//! missing instead of going negative.
+//!
+//! Money text: `"1pp 2gp 6sp 1cp"`, `"1161cp"`, `"11.61gp"` and
+//! `"1 platinum, 1 gold"` all parse with `str::parse::<Money>()`, and
+//! `Money::styled` prints any of the `MoneyStyle`s back in a form that parses.
+//!
+//! `Balance` is the one signed amount, for accounts that can go below zero.
+
+use std::error::Error;
+use std::fmt;
+use std::iter::Sum;
+use std::num::IntErrorKind;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use crate::{value_breakdown, Coin, Denomination};
+
+/// Money
+///
+/// Purpose: An amount of money, stored as a whole number of copper pieces.
+/// Type: `struct Money(u64)`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(u64);
+
+/// Balance
+///
+/// Purpose: A signed amount of money, such as an account that has paid out
+/// more than it received. Zero is never negative.
+/// Type: `struct Balance`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Balance {
+    negative: bool,
+    amount: Money,
+}
+
+/// MoneyError
+///
+/// Purpose: Why a money calculation could not produce a result.
+/// Type: `enum MoneyError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoneyError {
+    /// The result does not fit in a `u64` of copper pieces.
+    Overflow,
+    /// The result would be below zero; `shortfall` is how much is missing.
+    Negative { shortfall: Money },
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyError::Overflow => write!(f, "money amount overflowed"),
+            MoneyError::Negative { shortfall } => {
+                write!(f, "balance would be negative (short by {shortfall})")
+            }
+        }
+    }
+}
+
+impl Error for MoneyError {}
+
+/// ParseMoneyError
+///
+/// Purpose: Why a piece of text is not a valid amount of money. Variants
+/// carry the offending part of the text.
+/// Type: `enum ParseMoneyError`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoneyError {
+    /// The text has no amounts in it.
+    Empty,
+    /// A number could not be read, e.g. `"x3gp"` or `"1.2.3gp"`.
+    InvalidNumber(String),
+    /// A number has no unit after it, e.g. `"12"`.
+    MissingUnit(String),
+    /// The unit is not a denomination code or name, e.g. `"3zz"`.
+    UnknownUnit(String),
+    /// The amount is not a whole number of copper pieces, e.g. `"0.5cp"`.
+    FractionalCopper(String),
+    /// The total does not fit in `Money`.
+    Overflow,
+}
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMoneyError::Empty => write!(f, "no amount given"),
+            ParseMoneyError::InvalidNumber(s) => write!(f, "invalid number in `{s}`"),
+            ParseMoneyError::MissingUnit(s) => write!(f, "`{s}` needs a unit such as gp or cp"),
+            ParseMoneyError::UnknownUnit(s) => write!(f, "unknown coin unit `{s}`"),
+            ParseMoneyError::FractionalCopper(s) => write!(f, "`{s}` is not a whole number of copper pieces"),
+            ParseMoneyError::Overflow => write!(f, "amount is too large"),
+        }
+    }
+}
+
+impl Error for ParseMoneyError {}
+
+/// MoneyStyle
+///
+/// Purpose: Ways to print an amount with `Money::styled`. For 1161 cp:
+/// - `Compact`   — "11.61gp"
+/// - `Verbose`   — "1 platinum, 1 gold, 6 silver, 1 copper"
+/// - `Breakdown` — "1pp 1gp 6sp 1cp"
+///
+/// `Verbose` and `Breakdown` use pp/gp/sp/cp like `value_breakdown` and omit
+/// zero entries.
+/// Type: `enum MoneyStyle`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoneyStyle {
+    Compact,
+    Verbose,
+    Breakdown,
+}
+
+/// StyledMoney
+///
+/// Purpose: An amount paired with a `MoneyStyle`, ready for `{}` printing.
+/// Type: `struct StyledMoney`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyledMoney {
+    money: Money,
+    style: MoneyStyle,
+}
+
+impl Money {
+    /// No money at all.
+    pub const ZERO: Money = Money(0);
+
+    /// from_cp
+    ///
+    /// Purpose: Build an amount from a number of copper pieces.
+    /// Type: `const fn from_cp(cp: u64) -> Money`
+    pub const fn from_cp(cp: u64) -> Money {
+        Money(cp)
+    }
+
+    /// cp
+    ///
+    /// Purpose: The amount as a raw number of copper pieces.
+    /// Type: `const fn cp(self) -> u64`
+    pub const fn cp(self) -> u64 {
+        self.0
+    }
+
+    /// checked_add
+    ///
+    /// Purpose: Add two amounts.
+    /// Returns: `Err(MoneyError::Overflow)` if the sum does not fit.
+    /// Type: `fn checked_add(self, rhs: Money) -> Result<Money, MoneyError>`
+    pub fn checked_add(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.0.checked_add(rhs.0).map(Money).ok_or(MoneyError::Overflow)
+    }
+
+    /// checked_sub
+    ///
+    /// Purpose: Subtract `rhs` from this amount.
+    /// Returns: `Err(MoneyError::Negative { .. })` if `rhs` is larger.
+    /// Type: `fn checked_sub(self, rhs: Money) -> Result<Money, MoneyError>`
+    pub fn checked_sub(self, rhs: Money) -> Result<Money, MoneyError> {
+        self.0.checked_sub(rhs.0).map(Money).ok_or_else(|| MoneyError::Negative {
+            shortfall: Money(rhs.0 - self.0),
+        })
+    }
+
+    /// checked_mul
+    ///
+    /// Purpose: Multiply this amount by a count (e.g. a price times a quantity).
+    /// Returns: `Err(MoneyError::Overflow)` if the product does not fit.
+    /// Type: `fn checked_mul(self, n: u32) -> Result<Money, MoneyError>`
+    pub fn checked_mul(self, n: u32) -> Result<Money, MoneyError> {
+        self.0.checked_mul(u64::from(n)).map(Money).ok_or(MoneyError::Overflow)
+    }
+
+    /// styled
+    ///
+    /// Purpose: Print this amount in a particular `MoneyStyle`.
+    /// Type: `fn styled(self, style: MoneyStyle) -> StyledMoney`
+    pub fn styled(self, style: MoneyStyle) -> StyledMoney {
+        StyledMoney { money: self, style }
+    }
+}
+
+impl fmt::Display for Money {
+    /// Print as a plain copper count, e.g. "1161 cp".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} cp", self.0)
+    }
+}
+
+impl Balance {
+    /// A balance of nothing.
+    pub const ZERO: Balance = Balance { negative: false, amount: Money::ZERO };
+
+    /// positive
+    ///
+    /// Purpose: A balance of `amount` in hand.
+    /// Type: `fn positive(amount: Money) -> Balance`
+    pub fn positive(amount: Money) -> Balance {
+        Balance { negative: false, amount }
+    }
+
+    /// negative
+    ///
+    /// Purpose: A balance of `amount` owed or paid out.
+    /// Type: `fn negative(amount: Money) -> Balance`
+    pub fn negative(amount: Money) -> Balance {
+        Balance { negative: amount != Money::ZERO, amount }
+    }
+
+    /// difference
+    ///
+    /// Purpose: `plus - minus`, which may be negative.
+    /// Type: `fn difference(plus: Money, minus: Money) -> Balance`
+    pub fn difference(plus: Money, minus: Money) -> Balance {
+        match plus - minus {
+            Ok(amount) => Balance::positive(amount),
+            Err(_) => Balance::negative(Money(minus.0 - plus.0)),
+        }
+    }
+
+    /// amount
+    ///
+    /// Purpose: The size of the balance, ignoring its sign.
+    /// Type: `fn amount(self) -> Money`
+    pub fn amount(self) -> Money {
+        self.amount
+    }
+
+    /// is_negative
+    ///
+    /// Purpose: True if the balance is below zero.
+    /// Type: `fn is_negative(self) -> bool`
+    pub fn is_negative(self) -> bool {
+        self.negative
+    }
+}
+
+impl fmt::Display for Balance {
+    /// Print as a signed copper count, e.g. "-200 cp".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.amount)
+    }
+}
+
+impl fmt::Display for StyledMoney {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cp = self.money.cp();
+        if self.style == MoneyStyle::Compact {
+            let (gp, rest) = (cp / 100, cp % 100);
+            return match rest {
+                0 => write!(f, "{gp}gp"),
+                _ if rest.is_multiple_of(10) => write!(f, "{gp}.{}gp", rest / 10),
+                _ => write!(f, "{gp}.{rest:02}gp"),
+            };
+        }
+
+        let (pp, gp, sp, cp) = value_breakdown(self.money);
+        let parts = [
+            (pp, Denomination::Platinum),
+            (gp, Denomination::Gold),
+            (sp, Denomination::Silver),
+            (cp, Denomination::Copper),
+        ];
+        let mut shown: Vec<&(u64, Denomination)> = parts.iter().filter(|(n, _)| *n > 0).collect();
+        if shown.is_empty() {
+            shown.push(&parts[3]); // "0cp" rather than nothing
+        }
+        for (i, (n, denom)) in shown.into_iter().enumerate() {
+            match self.style {
+                MoneyStyle::Verbose if i > 0 => write!(f, ", {n} {}", denom.name())?,
+                MoneyStyle::Verbose => write!(f, "{n} {}", denom.name())?,
+                _ if i > 0 => write!(f, " {n}{}", denom.display_code())?,
+                _ => write!(f, "{n}{}", denom.display_code())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// Parse amounts like "1pp 2gp 6sp 1cp", "1161 cp", "11.61gp" or
+    /// "1 platinum, 1 gold". Each part is a number and a unit (code or
+    /// name, any case), with or without a space between; parts are added up.
+    fn from_str(text: &str) -> Result<Money, ParseMoneyError> {
+        let text = text.replace(',', " ");
+        let mut tokens = text.split_whitespace();
+        let mut total = None;
+        while let Some(token) = tokens.next() {
+            let split = token.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(token.len());
+            let (number, unit) = token.split_at(split);
+            if number.is_empty() {
+                return Err(ParseMoneyError::InvalidNumber(token.to_string()));
+            }
+            let unit = match unit {
+                "" => tokens.next().ok_or_else(|| ParseMoneyError::MissingUnit(token.to_string()))?,
+                unit => unit,
+            };
+            let denom = Denomination::from_unit(unit).ok_or_else(|| ParseMoneyError::UnknownUnit(unit.to_string()))?;
+            let amount = parse_amount(number, denom)?;
+            total = Some(total.unwrap_or(Money::ZERO).checked_add(amount).map_err(|_| ParseMoneyError::Overflow)?);
+        }
+        total.ok_or(ParseMoneyError::Empty)
+    }
+}
+
+/// parse_amount
+///
+/// Purpose: Convert a decimal number of `denom` coins (e.g. "11.61" gold)
+/// into copper, requiring a whole number of copper pieces.
+/// Type: `fn parse_amount(number: &str, denom: Denomination) -> Result<Money, ParseMoneyError>`
+fn parse_amount(number: &str, denom: Denomination) -> Result<Money, ParseMoneyError> {
+    let invalid = || ParseMoneyError::InvalidNumber(number.to_string());
+    let unit = u64::from(denom.value_in_cp());
+    let (whole, frac) = match number.split_once('.') {
+        Some((whole, frac)) if !frac.is_empty() => (whole, frac.trim_end_matches('0')),
+        Some(_) => return Err(invalid()),
+        None => (number, ""),
+    };
+    let whole: u64 = match whole.parse() {
+        _ if whole.is_empty() => 0,
+        Ok(n) => n,
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => return Err(ParseMoneyError::Overflow),
+        Err(_) => return Err(invalid()),
+    };
+    let whole_cp = whole.checked_mul(unit).ok_or(ParseMoneyError::Overflow)?;
+    if frac.is_empty() {
+        return Ok(Money(whole_cp));
+    }
+
+    // The largest unit is 1000 cp, so more than three decimals can't be whole copper.
+    let fractional = || ParseMoneyError::FractionalCopper(format!("{number}{}", denom.display_code()));
+    if frac.len() > 3 {
+        return Err(fractional());
+    }
+    let digits: u64 = frac.parse().map_err(|_| invalid())?;
+    let scale = 10u64.pow(frac.len() as u32);
+    if !(digits * unit).is_multiple_of(scale) {
+        return Err(fractional());
+    }
+    whole_cp.checked_add(digits * unit / scale).map(Money).ok_or(ParseMoneyError::Overflow)
+}
+
+impl Add for Money {
+    type Output = Result<Money, MoneyError>;
+
+    fn add(self, rhs: Money) -> Self::Output {
+        self.checked_add(rhs)
+    }
+}
+
+impl Sub for Money {
+    type Output = Result<Money, MoneyError>;
+
+    fn sub(self, rhs: Money) -> Self::Output {
+        self.checked_sub(rhs)
+    }
+}
+
+impl Mul<u32> for Money {
+    type Output = Result<Money, MoneyError>;
+
+    fn mul(self, n: u32) -> Self::Output {
+        self.checked_mul(n)
+    }
+}
+
+impl Sum<Money> for Result<Money, MoneyError> {
+    /// Add up amounts, stopping at the first overflow.
+    fn sum<I: Iterator<Item = Money>>(mut iter: I) -> Self {
+        iter.try_fold(Money::ZERO, |acc, m| acc.checked_add(m))
+    }
+}
+
+impl Sum<Coin> for Result<Money, MoneyError> {
+    /// Total worth of owned coins (see `Coin::worth`).
+    fn sum<I: Iterator<Item = Coin>>(iter: I) -> Self {
+        iter.map(|c| c.worth()).sum()
+    }
+}
+
+impl<'a> Sum<&'a Coin> for Result<Money, MoneyError> {
+    /// Total worth of borrowed coins, e.g. `wallet.iter().sum()`.
+    fn sum<I: Iterator<Item = &'a Coin>>(iter: I) -> Self {
+        iter.map(|c| c.worth()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Money::from_cp(150);
+        let b = Money::from_cp(100);
+        assert_eq!(a + b, Ok(Money::from_cp(250)));
+        assert_eq!(a - b, Ok(Money::from_cp(50)));
+        assert_eq!(b - a, Err(MoneyError::Negative { shortfall: Money::from_cp(50) }));
+        assert_eq!(a * 3, Ok(Money::from_cp(450)));
+        assert!(a > b);
+    }
+
+    #[test]
+    fn test_balance_signs() {
+        let m = Money::from_cp;
+        assert_eq!(Balance::difference(m(50), m(200)), Balance::negative(m(150)));
+        assert_eq!(Balance::difference(m(200), m(50)), Balance::positive(m(150)));
+        assert_eq!(Balance::difference(m(7), m(7)), Balance::ZERO);
+        assert_eq!(Balance::negative(Money::ZERO), Balance::ZERO);
+        assert!(Balance::negative(m(1)).is_negative());
+        assert_eq!(Balance::negative(m(3)).amount(), m(3));
+        assert_eq!(Balance::negative(m(3)).to_string(), "-3 cp");
+        assert_eq!(Balance::ZERO.to_string(), "0 cp");
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let big = Money::from_cp(u64::MAX - 1);
+        assert_eq!(big + Money::from_cp(2), Err(MoneyError::Overflow));
+        assert_eq!(big * 2, Err(MoneyError::Overflow));
+        let sum: Result<Money, _> = [big, Money::from_cp(1), Money::from_cp(1)].into_iter().sum();
+        assert_eq!(sum, Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn test_parse_money_forms() {
+        let expected = Ok(Money::from_cp(1161));
+        assert_eq!("1pp 2gp 6sp 1cp".parse::<Money>(), Ok(Money::from_cp(1261)));
+        assert_eq!("1pp 1gp 6sp 1cp".parse::<Money>(), expected);
+        assert_eq!("1161cp".parse::<Money>(), expected);
+        assert_eq!("1161 cp".parse::<Money>(), expected);
+        assert_eq!("11.61gp".parse::<Money>(), expected);
+        assert_eq!("1 platinum, 1 Gold, 6 silver, 1 copper".parse::<Money>(), expected);
+        assert_eq!("0.02ep".parse::<Money>(), Ok(Money::from_cp(1)));
+        assert_eq!(".5gp".parse::<Money>(), Ok(Money::from_cp(50)));
+    }
+
+    #[test]
+    fn test_parse_money_errors() {
+        assert_eq!("".parse::<Money>(), Err(ParseMoneyError::Empty));
+        assert_eq!("12".parse::<Money>(), Err(ParseMoneyError::MissingUnit("12".into())));
+        assert_eq!("3zz".parse::<Money>(), Err(ParseMoneyError::UnknownUnit("zz".into())));
+        assert_eq!("gp".parse::<Money>(), Err(ParseMoneyError::InvalidNumber("gp".into())));
+        assert_eq!("1.2.3gp".parse::<Money>(), Err(ParseMoneyError::InvalidNumber("1.2.3".into())));
+        assert_eq!("0.5cp".parse::<Money>(), Err(ParseMoneyError::FractionalCopper("0.5cp".into())));
+        assert_eq!("1.0001pp".parse::<Money>(), Err(ParseMoneyError::FractionalCopper("1.0001pp".into())));
+        assert_eq!("99999999999999999999pp".parse::<Money>(), Err(ParseMoneyError::Overflow));
+        assert_eq!("18446744073709551615cp 1cp".parse::<Money>(), Err(ParseMoneyError::Overflow));
+        assert_eq!("3GP".parse::<Money>(), "3 GOLD".parse::<Money>());
+        assert_eq!("1Pp 2gP".parse::<Money>(), Ok(Money::from_cp(1200)));
+    }
+
+    #[test]
+    fn test_styles_round_trip() {
+        let m = Money::from_cp(1161);
+        assert_eq!(m.styled(MoneyStyle::Compact).to_string(), "11.61gp");
+        assert_eq!(m.styled(MoneyStyle::Verbose).to_string(), "1 platinum, 1 gold, 6 silver, 1 copper");
+        assert_eq!(m.styled(MoneyStyle::Breakdown).to_string(), "1pp 1gp 6sp 1cp");
+        assert_eq!(Money::from_cp(1005).styled(MoneyStyle::Breakdown).to_string(), "1pp 5cp");
+        assert_eq!(Money::from_cp(1150).styled(MoneyStyle::Compact).to_string(), "11.5gp");
+        assert_eq!(Money::ZERO.styled(MoneyStyle::Verbose).to_string(), "0 copper");
+
+        for cp in [0, 1, 9, 10, 99, 100, 1161, 20_050, 987_654_321] {
+            let m = Money::from_cp(cp);
+            for style in [MoneyStyle::Compact, MoneyStyle::Verbose, MoneyStyle::Breakdown] {
+                assert_eq!(m.styled(style).to_string().parse::<Money>(), Ok(m), "{style:?}");
+            }
+            assert_eq!(m.to_string().parse::<Money>(), Ok(m));
+        }
+    }
+}
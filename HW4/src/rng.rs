@@ -0,0 +1,119 @@
+//! rng — small seeded random number generator
+//!
+//! Simulations and replays must be reproducible, so everything random in
+//! this crate draws from an explicitly seeded `Rng` (SplitMix64). The same
+//! seed always gives the same sequence on every platform.
+
+/// Rng
+///
+/// Purpose: Deterministic pseudo-random numbers from a 64-bit seed.
+/// Type: `struct Rng`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// new
+    ///
+    /// Purpose: A generator that will produce the sequence for `seed`.
+    /// Type: `fn new(seed: u64) -> Rng`
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// next_u64
+    ///
+    /// Purpose: The next 64 random bits (SplitMix64 step).
+    /// Type: `fn next_u64(&mut self) -> u64`
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// below
+    ///
+    /// Purpose: A number in `0..n`. Panics if `n` is zero.
+    /// Type: `fn below(&mut self, n: u64) -> u64`
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Rng::below(0)");
+        // Multiply-shift keeps the bias negligible for game-sized ranges.
+        ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+    }
+
+    /// index
+    ///
+    /// Purpose: A random index into a collection of `len` items.
+    /// Type: `fn index(&mut self, len: usize) -> usize`
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// weighted
+    ///
+    /// Purpose: Pick an index with probability proportional to its weight.
+    /// Returns: `None` if all weights are zero.
+    /// Type: `fn weighted(&mut self, weights: &[u32]) -> Option<usize>`
+    pub fn weighted(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = self.below(total);
+        weights.iter().position(|&w| {
+            let hit = roll < u64::from(w);
+            roll = roll.saturating_sub(u64::from(w));
+            hit
+        })
+    }
+
+    /// shuffle
+    ///
+    /// Purpose: Shuffle a slice in place (Fisher–Yates).
+    /// Type: `fn shuffle<T>(&mut self, items: &mut [T])`
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..5).map(|_| c.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+    }
+
+    #[test]
+    fn test_ranges_and_weights() {
+        let mut rng = Rng::new(1);
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+        assert_eq!(rng.weighted(&[0, 0]), None);
+        assert!((0..100).all(|_| rng.weighted(&[0, 3, 0]) == Some(1)));
+
+        let mut counts = [0u32; 2];
+        for _ in 0..10_000 {
+            counts[rng.weighted(&[1, 3]).unwrap()] += 1;
+        }
+        assert!((7000..8000).contains(&counts[1]), "{counts:?}");
+
+        let mut items = [1, 2, 3, 4, 5];
+        rng.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, [1, 2, 3, 4, 5]);
+    }
+}
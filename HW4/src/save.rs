@@ -0,0 +1,1385 @@
+//! save — versioned save format for coins, wallets and ledgers
+//!
+//! Coins, and the wallets, purses and hoards made of them, can be saved in
+//! two forms:
+//! - a compact binary form: `HWC` magic, a `u16` version, a `u32` coin count,
+//!   then one fixed-size record per coin, all little-endian;
+//! - human-readable JSON: `{"version": 2, "coins": [{"denom": "Gold", ...}]}`.
+//!
+//! A `Ledger` is saved the same two ways: in binary after an `HWL` magic, as
+//! its accounts (kind and name), then its transactions (timestamp, memo,
+//! entries, coins and the transaction each one reverses); in JSON as
+//! `{"version": 2, "accounts": [...], "transactions": [...]}`.
+//!
+//! Both carry `FORMAT_VERSION`. Loading an older version decodes it as it
+//! was written and then runs the migrations up to the current version.
+//!
+//! Notes:
+//! - Version history: 1 — coins without condition; 2 — coins carry a
+//!   `Condition` (grade, purity, uses), and ledgers can be saved. Version 1
+//!   coins load as fresh from the mint.
+//! - In binary, enums are stored as fixed tags (`material_tag` etc.), and in
+//!   JSON by fixed names (`material_name` etc.). Both are exhaustive matches,
+//!   so a new variant (a new `Material`, say) does not compile until it gets
+//!   the next unused tag and a name; older saves simply never contain it.
+//!   Renumbering, renaming or removing a variant needs a new version and a
+//!   migration that maps the old tags and names.
+//! - A loaded ledger is rebuilt by posting every transaction again, so it
+//!   passes the same checks as a live one (balanced entries, known accounts,
+//!   reversals of earlier transactions only).
+//! - Loading never panics: corrupt, truncated or unknown data is a
+//!   `SaveError`. Saving fails only if a count does not fit the format.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::condition::{Condition, Grade};
+use crate::ledger::{AccountId, AccountKind, Entry, Ledger, LedgerError, Side, TxId};
+use crate::money::Money;
+use crate::wallet::Wallet;
+use crate::{Coin, Denomination, Material, Mint};
+
+/// The version written by every save function.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// The first version that can hold a ledger.
+const LEDGER_SINCE: u16 = 2;
+
+/// Start of every binary coin save.
+const MAGIC: &[u8; 3] = b"HWC";
+
+/// Start of every binary ledger save.
+const LEDGER_MAGIC: &[u8; 3] = b"HWL";
+
+/// Bytes per coin in each binary version.
+const V1_RECORD: usize = 9;
+const V2_RECORD: usize = 15;
+
+/// JSON nested deeper than this is rejected rather than risking the stack.
+const MAX_DEPTH: usize = 32;
+
+/// SaveError
+///
+/// Purpose: Why data could not be saved or loaded.
+/// Type: `enum SaveError`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    /// Binary data does not start with the expected magic.
+    BadMagic,
+    /// Written by a newer (or unknown) version of the game.
+    UnsupportedVersion(u16),
+    /// Binary data ends in the middle of a value.
+    UnexpectedEnd,
+    /// Extra bytes after the end of the save.
+    TrailingData,
+    UnknownTag { kind: &'static str, tag: u8 },
+    UnknownName { kind: &'static str, name: String },
+    MissingField(&'static str),
+    /// A field holds the wrong type or an out-of-range value.
+    InvalidField(&'static str),
+    /// The text is not valid JSON.
+    Json { offset: usize, message: &'static str },
+    /// Too many of something to fit the format's `u32` counts.
+    TooLarge(&'static str),
+    /// The saved transactions do not make valid books.
+    Ledger(LedgerError),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::BadMagic => write!(f, "not a save of this kind (bad magic)"),
+            SaveError::UnsupportedVersion(v) => {
+                write!(f, "save format version {v} is not supported (up to {FORMAT_VERSION})")
+            }
+            SaveError::UnexpectedEnd => write!(f, "save data ends unexpectedly"),
+            SaveError::TrailingData => write!(f, "unexpected data after the end of the save"),
+            SaveError::UnknownTag { kind, tag } => write!(f, "unknown {kind} tag {tag}"),
+            SaveError::UnknownName { kind, name } => write!(f, "unknown {kind} `{name}`"),
+            SaveError::MissingField(field) => write!(f, "missing field `{field}`"),
+            SaveError::InvalidField(field) => write!(f, "invalid value for `{field}`"),
+            SaveError::Json { offset, message } => write!(f, "bad JSON at byte {offset}: {message}"),
+            SaveError::TooLarge(what) => write!(f, "too many {what} to save"),
+            SaveError::Ledger(e) => write!(f, "saved ledger is invalid: {e}"),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<LedgerError> for SaveError {
+    fn from(e: LedgerError) -> Self {
+        SaveError::Ledger(e)
+    }
+}
+
+/// CoinV1
+///
+/// Purpose: A coin as saved by format version 1, before coins had a
+/// condition.
+/// Type: `struct CoinV1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoinV1 {
+    pub denom: Denomination,
+    pub material: Material,
+    pub diameter_mm: u16,
+    pub thickness_mm: u16,
+    pub year: u16,
+    pub mint: Mint,
+}
+
+impl CoinV1 {
+    /// migrate
+    ///
+    /// Purpose: Upgrade one version 1 coin. It predates wear and
+    /// debasement, so it comes back fresh from the mint.
+    /// Type: `fn migrate(self) -> Coin`
+    pub fn migrate(self) -> Coin {
+        Coin {
+            denom: self.denom,
+            material: self.material,
+            diameter_mm: self.diameter_mm,
+            thickness_mm: self.thickness_mm,
+            year: self.year,
+            mint: self.mint,
+            condition: Condition::MINT,
+        }
+    }
+}
+
+/// migrate_v1
+///
+/// Purpose: Upgrade version 1 coins to the current `Coin`.
+/// Type: `fn migrate_v1(coins: Vec<CoinV1>) -> Vec<Coin>`
+pub fn migrate_v1(coins: Vec<CoinV1>) -> Vec<Coin> {
+    coins.into_iter().map(CoinV1::migrate).collect()
+}
+
+/// denomination_tag
+///
+/// Purpose: The binary tag for each denomination. Exhaustive, so a new
+/// variant will not compile until it is given a tag.
+fn denomination_tag(denom: Denomination) -> u8 {
+    match denom {
+        Denomination::Copper => 0,
+        Denomination::Silver => 1,
+        Denomination::Electrum => 2,
+        Denomination::Gold => 3,
+        Denomination::Platinum => 4,
+    }
+}
+
+/// denomination_from_tag
+///
+/// Purpose: The denomination stored under `tag`.
+fn denomination_from_tag(tag: u8) -> Result<Denomination, SaveError> {
+    match tag {
+        0 => Ok(Denomination::Copper),
+        1 => Ok(Denomination::Silver),
+        2 => Ok(Denomination::Electrum),
+        3 => Ok(Denomination::Gold),
+        4 => Ok(Denomination::Platinum),
+        _ => Err(SaveError::UnknownTag { kind: "denomination", tag }),
+    }
+}
+
+/// denomination_name
+///
+/// Purpose: The JSON name for each denomination. Like the tags, these never
+/// change once written.
+fn denomination_name(denom: Denomination) -> &'static str {
+    match denom {
+        Denomination::Copper => "Copper",
+        Denomination::Silver => "Silver",
+        Denomination::Electrum => "Electrum",
+        Denomination::Gold => "Gold",
+        Denomination::Platinum => "Platinum",
+    }
+}
+
+/// denomination_from_name
+///
+/// Purpose: The denomination saved as `name`.
+fn denomination_from_name(name: &str) -> Result<Denomination, SaveError> {
+    match name {
+        "Copper" => Ok(Denomination::Copper),
+        "Silver" => Ok(Denomination::Silver),
+        "Electrum" => Ok(Denomination::Electrum),
+        "Gold" => Ok(Denomination::Gold),
+        "Platinum" => Ok(Denomination::Platinum),
+        _ => Err(SaveError::UnknownName { kind: "denomination", name: name.to_string() }),
+    }
+}
+
+/// material_tag
+///
+/// Purpose: The binary tag for each material. New materials take the next
+/// unused number; existing numbers never change.
+fn material_tag(material: Material) -> u8 {
+    match material {
+        Material::Copper => 0,
+        Material::Silver => 1,
+        Material::Electrum => 2,
+        Material::Gold => 3,
+        Material::Platinum => 4,
+        Material::Mithril => 5,
+        Material::Obsidian => 6,
+    }
+}
+
+/// material_from_tag
+///
+/// Purpose: The material stored under `tag`.
+fn material_from_tag(tag: u8) -> Result<Material, SaveError> {
+    match tag {
+        0 => Ok(Material::Copper),
+        1 => Ok(Material::Silver),
+        2 => Ok(Material::Electrum),
+        3 => Ok(Material::Gold),
+        4 => Ok(Material::Platinum),
+        5 => Ok(Material::Mithril),
+        6 => Ok(Material::Obsidian),
+        _ => Err(SaveError::UnknownTag { kind: "material", tag }),
+    }
+}
+
+/// material_name
+///
+/// Purpose: The JSON name for each material.
+fn material_name(material: Material) -> &'static str {
+    match material {
+        Material::Copper => "Copper",
+        Material::Silver => "Silver",
+        Material::Electrum => "Electrum",
+        Material::Gold => "Gold",
+        Material::Platinum => "Platinum",
+        Material::Mithril => "Mithril",
+        Material::Obsidian => "Obsidian",
+    }
+}
+
+/// material_from_name
+///
+/// Purpose: The material saved as `name`.
+fn material_from_name(name: &str) -> Result<Material, SaveError> {
+    match name {
+        "Copper" => Ok(Material::Copper),
+        "Silver" => Ok(Material::Silver),
+        "Electrum" => Ok(Material::Electrum),
+        "Gold" => Ok(Material::Gold),
+        "Platinum" => Ok(Material::Platinum),
+        "Mithril" => Ok(Material::Mithril),
+        "Obsidian" => Ok(Material::Obsidian),
+        _ => Err(SaveError::UnknownName { kind: "material", name: name.to_string() }),
+    }
+}
+
+/// mint_tag
+///
+/// Purpose: The binary tag for each mint.
+fn mint_tag(mint: Mint) -> u8 {
+    match mint {
+        Mint::Capital => 0,
+        Mint::Coastal => 1,
+        Mint::Mountain => 2,
+        Mint::Desert => 3,
+    }
+}
+
+/// mint_from_tag
+///
+/// Purpose: The mint stored under `tag`.
+fn mint_from_tag(tag: u8) -> Result<Mint, SaveError> {
+    match tag {
+        0 => Ok(Mint::Capital),
+        1 => Ok(Mint::Coastal),
+        2 => Ok(Mint::Mountain),
+        3 => Ok(Mint::Desert),
+        _ => Err(SaveError::UnknownTag { kind: "mint", tag }),
+    }
+}
+
+/// mint_name
+///
+/// Purpose: The JSON name for each mint.
+fn mint_name(mint: Mint) -> &'static str {
+    match mint {
+        Mint::Capital => "Capital",
+        Mint::Coastal => "Coastal",
+        Mint::Mountain => "Mountain",
+        Mint::Desert => "Desert",
+    }
+}
+
+/// mint_from_name
+///
+/// Purpose: The mint saved as `name`.
+fn mint_from_name(name: &str) -> Result<Mint, SaveError> {
+    match name {
+        "Capital" => Ok(Mint::Capital),
+        "Coastal" => Ok(Mint::Coastal),
+        "Mountain" => Ok(Mint::Mountain),
+        "Desert" => Ok(Mint::Desert),
+        _ => Err(SaveError::UnknownName { kind: "mint", name: name.to_string() }),
+    }
+}
+
+/// grade_tag
+///
+/// Purpose: The binary tag for each grade.
+fn grade_tag(grade: Grade) -> u8 {
+    match grade {
+        Grade::Worn => 0,
+        Grade::Good => 1,
+        Grade::Fine => 2,
+        Grade::VeryFine => 3,
+        Grade::ExtremelyFine => 4,
+        Grade::Uncirculated => 5,
+    }
+}
+
+/// grade_from_tag
+///
+/// Purpose: The grade stored under `tag`.
+fn grade_from_tag(tag: u8) -> Result<Grade, SaveError> {
+    match tag {
+        0 => Ok(Grade::Worn),
+        1 => Ok(Grade::Good),
+        2 => Ok(Grade::Fine),
+        3 => Ok(Grade::VeryFine),
+        4 => Ok(Grade::ExtremelyFine),
+        5 => Ok(Grade::Uncirculated),
+        _ => Err(SaveError::UnknownTag { kind: "grade", tag }),
+    }
+}
+
+/// grade_name
+///
+/// Purpose: The JSON name for each grade.
+fn grade_name(grade: Grade) -> &'static str {
+    match grade {
+        Grade::Worn => "Worn",
+        Grade::Good => "Good",
+        Grade::Fine => "Fine",
+        Grade::VeryFine => "VeryFine",
+        Grade::ExtremelyFine => "ExtremelyFine",
+        Grade::Uncirculated => "Uncirculated",
+    }
+}
+
+/// grade_from_name
+///
+/// Purpose: The grade saved as `name`.
+fn grade_from_name(name: &str) -> Result<Grade, SaveError> {
+    match name {
+        "Worn" => Ok(Grade::Worn),
+        "Good" => Ok(Grade::Good),
+        "Fine" => Ok(Grade::Fine),
+        "VeryFine" => Ok(Grade::VeryFine),
+        "ExtremelyFine" => Ok(Grade::ExtremelyFine),
+        "Uncirculated" => Ok(Grade::Uncirculated),
+        _ => Err(SaveError::UnknownName { kind: "grade", name: name.to_string() }),
+    }
+}
+
+/// account_kind_tag
+///
+/// Purpose: The binary tag for each kind of ledger account.
+fn account_kind_tag(kind: AccountKind) -> u8 {
+    match kind {
+        AccountKind::Player => 0,
+        AccountKind::Merchant => 1,
+        AccountKind::Bank => 2,
+        AccountKind::Mint => 3,
+    }
+}
+
+/// account_kind_from_tag
+///
+/// Purpose: The account kind stored under `tag`.
+fn account_kind_from_tag(tag: u8) -> Result<AccountKind, SaveError> {
+    match tag {
+        0 => Ok(AccountKind::Player),
+        1 => Ok(AccountKind::Merchant),
+        2 => Ok(AccountKind::Bank),
+        3 => Ok(AccountKind::Mint),
+        _ => Err(SaveError::UnknownTag { kind: "account kind", tag }),
+    }
+}
+
+/// account_kind_name
+///
+/// Purpose: The JSON name for each kind of ledger account.
+fn account_kind_name(kind: AccountKind) -> &'static str {
+    match kind {
+        AccountKind::Player => "Player",
+        AccountKind::Merchant => "Merchant",
+        AccountKind::Bank => "Bank",
+        AccountKind::Mint => "Mint",
+    }
+}
+
+/// account_kind_from_name
+///
+/// Purpose: The account kind saved as `name`.
+fn account_kind_from_name(name: &str) -> Result<AccountKind, SaveError> {
+    match name {
+        "Player" => Ok(AccountKind::Player),
+        "Merchant" => Ok(AccountKind::Merchant),
+        "Bank" => Ok(AccountKind::Bank),
+        "Mint" => Ok(AccountKind::Mint),
+        _ => Err(SaveError::UnknownName { kind: "account kind", name: name.to_string() }),
+    }
+}
+
+/// side_tag
+///
+/// Purpose: The binary tag for each side of an entry.
+fn side_tag(side: Side) -> u8 {
+    match side {
+        Side::Debit => 0,
+        Side::Credit => 1,
+    }
+}
+
+/// side_from_tag
+///
+/// Purpose: The side stored under `tag`.
+fn side_from_tag(tag: u8) -> Result<Side, SaveError> {
+    match tag {
+        0 => Ok(Side::Debit),
+        1 => Ok(Side::Credit),
+        _ => Err(SaveError::UnknownTag { kind: "side", tag }),
+    }
+}
+
+/// side_name
+///
+/// Purpose: The JSON name for each side of an entry.
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Debit => "Debit",
+        Side::Credit => "Credit",
+    }
+}
+
+/// side_from_name
+///
+/// Purpose: The side saved as `name`.
+fn side_from_name(name: &str) -> Result<Side, SaveError> {
+    match name {
+        "Debit" => Ok(Side::Debit),
+        "Credit" => Ok(Side::Credit),
+        _ => Err(SaveError::UnknownName { kind: "side", name: name.to_string() }),
+    }
+}
+
+/// put_count
+///
+/// Purpose: Write a count or index as a `u32`, or fail with `TooLarge`
+/// rather than truncate it.
+fn put_count(out: &mut Vec<u8>, n: usize, what: &'static str) -> Result<(), SaveError> {
+    let n = u32::try_from(n).map_err(|_| SaveError::TooLarge(what))?;
+    out.extend_from_slice(&n.to_le_bytes());
+    Ok(())
+}
+
+/// put_str
+///
+/// Purpose: Write a string as its byte length, then its UTF-8 bytes.
+fn put_str(out: &mut Vec<u8>, s: &str, what: &'static str) -> Result<(), SaveError> {
+    put_count(out, s.len(), what)?;
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// put_coin
+///
+/// Purpose: Write one coin as a current-version record.
+fn put_coin(out: &mut Vec<u8>, coin: &Coin) {
+    out.push(denomination_tag(coin.denom));
+    out.push(material_tag(coin.material));
+    out.extend_from_slice(&coin.diameter_mm.to_le_bytes());
+    out.extend_from_slice(&coin.thickness_mm.to_le_bytes());
+    out.extend_from_slice(&coin.year.to_le_bytes());
+    out.push(mint_tag(coin.mint));
+    out.push(grade_tag(coin.condition.grade));
+    out.push(coin.condition.purity_pct);
+    out.extend_from_slice(&coin.condition.uses.to_le_bytes());
+}
+
+/// to_binary
+///
+/// Purpose: Save coins in the compact binary form, current version.
+/// Type: `fn to_binary(coins: &[Coin]) -> Result<Vec<u8>, SaveError>`
+pub fn to_binary(coins: &[Coin]) -> Result<Vec<u8>, SaveError> {
+    let mut out = Vec::with_capacity(MAGIC.len() + 6 + coins.len() * V2_RECORD);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    put_count(&mut out, coins.len(), "coins")?;
+    for coin in coins {
+        put_coin(&mut out, coin);
+    }
+    Ok(out)
+}
+
+/// Reader
+///
+/// Purpose: Reads little-endian values from a byte slice, failing instead
+/// of panicking when it runs out.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SaveError> {
+        if self.bytes.len() < n {
+            return Err(SaveError::UnexpectedEnd);
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SaveError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SaveError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SaveError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, SaveError> {
+        let mut b = [0; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(b))
+    }
+
+    /// A string written by `put_str`.
+    fn string(&mut self, field: &'static str) -> Result<String, SaveError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SaveError::InvalidField(field))
+    }
+
+    /// The magic and version at the start of a save.
+    fn header(&mut self, magic: &[u8; 3]) -> Result<u16, SaveError> {
+        if self.take(magic.len()).map_err(|_| SaveError::BadMagic)? != magic {
+            return Err(SaveError::BadMagic);
+        }
+        match self.u16()? {
+            v @ 1..=FORMAT_VERSION => Ok(v),
+            v => Err(SaveError::UnsupportedVersion(v)),
+        }
+    }
+
+    /// A version 1 record: the coin without its condition.
+    fn coin_v1(&mut self) -> Result<CoinV1, SaveError> {
+        Ok(CoinV1 {
+            denom: denomination_from_tag(self.u8()?)?,
+            material: material_from_tag(self.u8()?)?,
+            diameter_mm: self.u16()?,
+            thickness_mm: self.u16()?,
+            year: self.u16()?,
+            mint: mint_from_tag(self.u8()?)?,
+        })
+    }
+
+    /// A coin record as written by `version`, migrated to the current `Coin`.
+    fn coin(&mut self, version: u16) -> Result<Coin, SaveError> {
+        let mut coin = self.coin_v1()?.migrate();
+        if version >= 2 {
+            let grade = grade_from_tag(self.u8()?)?;
+            let purity_pct = self.u8()?;
+            if purity_pct > 100 {
+                return Err(SaveError::InvalidField("purity_pct"));
+            }
+            coin.condition = Condition { grade, purity_pct, uses: self.u32()? };
+        }
+        Ok(coin)
+    }
+}
+
+/// from_binary
+///
+/// Purpose: Load coins saved by `to_binary`, from any supported version.
+/// Type: `fn from_binary(bytes: &[u8]) -> Result<Vec<Coin>, SaveError>`
+pub fn from_binary(bytes: &[u8]) -> Result<Vec<Coin>, SaveError> {
+    let mut r = Reader { bytes };
+    let version = r.header(MAGIC)?;
+    let record = if version == 1 { V1_RECORD } else { V2_RECORD };
+    let count = r.u32()? as usize;
+    // Check the length up front so a corrupt count can't make us allocate wildly.
+    match count.checked_mul(record) {
+        Some(len) if len == r.bytes.len() => {}
+        Some(len) if len < r.bytes.len() => return Err(SaveError::TrailingData),
+        _ => return Err(SaveError::UnexpectedEnd),
+    }
+    (0..count).map(|_| r.coin(version)).collect()
+}
+
+/// ledger_to_binary
+///
+/// Purpose: Save a ledger's accounts and transactions in the binary form,
+/// current version.
+/// Type: `fn ledger_to_binary(ledger: &Ledger) -> Result<Vec<u8>, SaveError>`
+pub fn ledger_to_binary(ledger: &Ledger) -> Result<Vec<u8>, SaveError> {
+    let mut out = LEDGER_MAGIC.to_vec();
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    put_count(&mut out, ledger.accounts().count(), "accounts")?;
+    for (_, account) in ledger.accounts() {
+        out.push(account_kind_tag(account.kind));
+        put_str(&mut out, &account.name, "bytes in an account name")?;
+    }
+    put_count(&mut out, ledger.transactions().len(), "transactions")?;
+    for tx in ledger.transactions() {
+        out.extend_from_slice(&tx.timestamp.to_le_bytes());
+        put_str(&mut out, &tx.memo, "bytes in a memo")?;
+        put_count(&mut out, tx.entries.len(), "entries")?;
+        for entry in &tx.entries {
+            put_count(&mut out, entry.account.0, "accounts")?;
+            out.push(side_tag(entry.side));
+            out.extend_from_slice(&entry.amount.cp().to_le_bytes());
+        }
+        put_count(&mut out, tx.coins.len(), "coins")?;
+        for coin in &tx.coins {
+            put_coin(&mut out, coin);
+        }
+        match tx.reverses {
+            None => out.push(0),
+            Some(original) => {
+                out.push(1);
+                put_count(&mut out, original.0, "transactions")?;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// ledger_from_binary
+///
+/// Purpose: Load a ledger saved by `ledger_to_binary`, reposting every
+/// transaction so the books are checked as they load.
+/// Type: `fn ledger_from_binary(bytes: &[u8]) -> Result<Ledger, SaveError>`
+pub fn ledger_from_binary(bytes: &[u8]) -> Result<Ledger, SaveError> {
+    let mut r = Reader { bytes };
+    let version = r.header(LEDGER_MAGIC)?;
+    if version < LEDGER_SINCE {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    // Counts are not trusted for allocation: every item read uses up bytes,
+    // so a corrupt count runs out of data instead.
+    let mut ledger = Ledger::new();
+    for _ in 0..r.u32()? {
+        let kind = account_kind_from_tag(r.u8()?)?;
+        ledger.open_account(&r.string("name")?, kind);
+    }
+    for _ in 0..r.u32()? {
+        let timestamp = r.u64()?;
+        let memo = r.string("memo")?;
+        let mut entries = Vec::new();
+        for _ in 0..r.u32()? {
+            let account = AccountId(r.u32()? as usize);
+            let side = side_from_tag(r.u8()?)?;
+            entries.push(Entry { account, side, amount: Money::from_cp(r.u64()?) });
+        }
+        let mut coins = Vec::new();
+        for _ in 0..r.u32()? {
+            coins.push(r.coin(version)?);
+        }
+        let reverses = match r.u8()? {
+            0 => None,
+            1 => Some(TxId(r.u32()? as usize)),
+            _ => return Err(SaveError::InvalidField("reverses")),
+        };
+        ledger.post_with(timestamp, &memo, entries, coins, reverses)?;
+    }
+    if !r.bytes.is_empty() {
+        return Err(SaveError::TrailingData);
+    }
+    Ok(ledger)
+}
+
+/// json_string
+///
+/// Purpose: `s` as a quoted JSON string, escaping quotes, backslashes and
+/// control characters.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// coin_json
+///
+/// Purpose: One coin as a single-line JSON object. Every string in it is a
+/// fixed name, so nothing needs escaping.
+fn coin_json(c: &Coin) -> String {
+    format!(
+        "{{\"denom\": \"{}\", \"material\": \"{}\", \"diameter_mm\": {}, \"thickness_mm\": {}, \"year\": {}, \"mint\": \"{}\", \
+         \"condition\": {{\"grade\": \"{}\", \"purity_pct\": {}, \"uses\": {}}}}}",
+        denomination_name(c.denom),
+        material_name(c.material),
+        c.diameter_mm,
+        c.thickness_mm,
+        c.year,
+        mint_name(c.mint),
+        grade_name(c.condition.grade),
+        c.condition.purity_pct,
+        c.condition.uses
+    )
+}
+
+/// coin_from_json
+///
+/// Purpose: A coin object as written by `version`, migrated to the current
+/// `Coin`. Unknown fields are ignored.
+fn coin_from_json(item: &Json, version: u16) -> Result<Coin, SaveError> {
+    let mut coin = CoinV1 {
+        denom: denomination_from_name(item.get("denom")?.string("denom")?)?,
+        material: material_from_name(item.get("material")?.string("material")?)?,
+        diameter_mm: item.get("diameter_mm")?.small("diameter_mm")?,
+        thickness_mm: item.get("thickness_mm")?.small("thickness_mm")?,
+        year: item.get("year")?.small("year")?,
+        mint: mint_from_name(item.get("mint")?.string("mint")?)?,
+    }
+    .migrate();
+    if version >= 2 {
+        let condition = item.get("condition")?;
+        let purity_pct = u8::try_from(condition.get("purity_pct")?.number("purity_pct")?)
+            .ok()
+            .filter(|&p| p <= 100)
+            .ok_or(SaveError::InvalidField("purity_pct"))?;
+        coin.condition = Condition {
+            grade: grade_from_name(condition.get("grade")?.string("grade")?)?,
+            purity_pct,
+            uses: u32::try_from(condition.get("uses")?.number("uses")?).map_err(|_| SaveError::InvalidField("uses"))?,
+        };
+    }
+    Ok(coin)
+}
+
+/// to_json
+///
+/// Purpose: Save coins as human-readable JSON, one coin per line.
+/// Type: `fn to_json(coins: &[Coin]) -> String`
+pub fn to_json(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return format!("{{\"version\": {FORMAT_VERSION}, \"coins\": []}}\n");
+    }
+    let lines: Vec<String> = coins.iter().map(|c| format!("    {}", coin_json(c))).collect();
+    format!("{{\n  \"version\": {FORMAT_VERSION},\n  \"coins\": [\n{}\n  ]\n}}\n", lines.join(",\n"))
+}
+
+/// parse_json
+///
+/// Purpose: Parse a whole JSON save and return its root and version,
+/// which must be at least `since`.
+fn parse_json(text: &str, since: u16) -> Result<(Json, u16), SaveError> {
+    let mut parser = JsonParser { text, pos: 0 };
+    let root = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos != text.len() {
+        return Err(parser.error("unexpected text after the end"));
+    }
+    let version = u16::try_from(root.get("version")?.number("version")?).map_err(|_| SaveError::InvalidField("version"))?;
+    if !(since..=FORMAT_VERSION).contains(&version) {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    Ok((root, version))
+}
+
+/// from_json
+///
+/// Purpose: Load coins saved by `to_json`, from any supported version.
+/// Unknown fields are ignored.
+/// Type: `fn from_json(text: &str) -> Result<Vec<Coin>, SaveError>`
+pub fn from_json(text: &str) -> Result<Vec<Coin>, SaveError> {
+    let (root, version) = parse_json(text, 1)?;
+    root.get("coins")?.array("coins")?.iter().map(|item| coin_from_json(item, version)).collect()
+}
+
+/// ledger_to_json
+///
+/// Purpose: Save a ledger as human-readable JSON, one account or
+/// transaction per line.
+/// Type: `fn ledger_to_json(ledger: &Ledger) -> String`
+pub fn ledger_to_json(ledger: &Ledger) -> String {
+    let accounts: Vec<String> = ledger
+        .accounts()
+        .map(|(_, a)| format!("    {{\"name\": {}, \"kind\": \"{}\"}}", json_string(&a.name), account_kind_name(a.kind)))
+        .collect();
+    let transactions: Vec<String> = ledger
+        .transactions()
+        .iter()
+        .map(|tx| {
+            let entries: Vec<String> = tx
+                .entries
+                .iter()
+                .map(|e| format!("{{\"account\": {}, \"side\": \"{}\", \"amount\": {}}}", e.account.0, side_name(e.side), e.amount.cp()))
+                .collect();
+            let coins: Vec<String> = tx.coins.iter().map(coin_json).collect();
+            let reverses = tx.reverses.map_or("null".to_string(), |original| original.0.to_string());
+            format!(
+                "    {{\"timestamp\": {}, \"memo\": {}, \"entries\": [{}], \"coins\": [{}], \"reverses\": {reverses}}}",
+                tx.timestamp,
+                json_string(&tx.memo),
+                entries.join(", "),
+                coins.join(", ")
+            )
+        })
+        .collect();
+    let list = |lines: Vec<String>| if lines.is_empty() { "[]".to_string() } else { format!("[\n{}\n  ]", lines.join(",\n")) };
+    format!(
+        "{{\n  \"version\": {FORMAT_VERSION},\n  \"accounts\": {},\n  \"transactions\": {}\n}}\n",
+        list(accounts),
+        list(transactions)
+    )
+}
+
+/// ledger_from_json
+///
+/// Purpose: Load a ledger saved by `ledger_to_json`, reposting every
+/// transaction so the books are checked as they load. Unknown fields are
+/// ignored.
+/// Type: `fn ledger_from_json(text: &str) -> Result<Ledger, SaveError>`
+pub fn ledger_from_json(text: &str) -> Result<Ledger, SaveError> {
+    let (root, version) = parse_json(text, LEDGER_SINCE)?;
+    let index = |value: &Json, field: &'static str| {
+        usize::try_from(value.number(field)?).map_err(|_| SaveError::InvalidField(field))
+    };
+    let mut ledger = Ledger::new();
+    for account in root.get("accounts")?.array("accounts")? {
+        let kind = account_kind_from_name(account.get("kind")?.string("kind")?)?;
+        ledger.open_account(account.get("name")?.string("name")?, kind);
+    }
+    for tx in root.get("transactions")?.array("transactions")? {
+        let mut entries = Vec::new();
+        for entry in tx.get("entries")?.array("entries")? {
+            entries.push(Entry {
+                account: AccountId(index(entry.get("account")?, "account")?),
+                side: side_from_name(entry.get("side")?.string("side")?)?,
+                amount: Money::from_cp(entry.get("amount")?.number("amount")?),
+            });
+        }
+        let coins = tx.get("coins")?.array("coins")?.iter().map(|c| coin_from_json(c, version)).collect::<Result<_, _>>()?;
+        let reverses = match tx.get("reverses")? {
+            Json::Null => None,
+            original => Some(TxId(index(original, "reverses")?)),
+        };
+        let timestamp = tx.get("timestamp")?.number("timestamp")?;
+        ledger.post_with(timestamp, tx.get("memo")?.string("memo")?, entries, coins, reverses)?;
+    }
+    Ok(ledger)
+}
+
+/// wallet_to_binary
+///
+/// Purpose: Save every coin in a wallet in the binary form.
+/// Type: `fn wallet_to_binary(wallet: &Wallet) -> Result<Vec<u8>, SaveError>`
+pub fn wallet_to_binary(wallet: &Wallet) -> Result<Vec<u8>, SaveError> {
+    to_binary(&wallet.coins().copied().collect::<Vec<_>>())
+}
+
+/// wallet_from_binary
+///
+/// Purpose: Load a wallet saved by `wallet_to_binary` (or any binary coin save).
+/// Type: `fn wallet_from_binary(bytes: &[u8]) -> Result<Wallet, SaveError>`
+pub fn wallet_from_binary(bytes: &[u8]) -> Result<Wallet, SaveError> {
+    Ok(from_binary(bytes)?.into_iter().collect())
+}
+
+/// wallet_to_json
+///
+/// Purpose: Save every coin in a wallet as JSON.
+/// Type: `fn wallet_to_json(wallet: &Wallet) -> String`
+pub fn wallet_to_json(wallet: &Wallet) -> String {
+    to_json(&wallet.coins().copied().collect::<Vec<_>>())
+}
+
+/// wallet_from_json
+///
+/// Purpose: Load a wallet saved by `wallet_to_json` (or any JSON coin save).
+/// Type: `fn wallet_from_json(text: &str) -> Result<Wallet, SaveError>`
+pub fn wallet_from_json(text: &str) -> Result<Wallet, SaveError> {
+    Ok(from_json(text)?.into_iter().collect())
+}
+
+/// Json
+///
+/// Purpose: A parsed JSON value. Numbers are limited to whole, non-negative
+/// values, which is all the save format uses.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// get
+    ///
+    /// Purpose: A field of an object.
+    fn get(&self, field: &'static str) -> Result<&Json, SaveError> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == field).map(|(_, v)| v).ok_or(SaveError::MissingField(field)),
+            _ => Err(SaveError::InvalidField(field)),
+        }
+    }
+
+    fn number(&self, field: &'static str) -> Result<u64, SaveError> {
+        match self {
+            Json::Number(n) => Ok(*n),
+            _ => Err(SaveError::InvalidField(field)),
+        }
+    }
+
+    fn small(&self, field: &'static str) -> Result<u16, SaveError> {
+        u16::try_from(self.number(field)?).map_err(|_| SaveError::InvalidField(field))
+    }
+
+    fn string(&self, field: &'static str) -> Result<&str, SaveError> {
+        match self {
+            Json::String(s) => Ok(s),
+            _ => Err(SaveError::InvalidField(field)),
+        }
+    }
+    fn array(&self, field: &'static str) -> Result<&[Json], SaveError> {
+        match self {
+            Json::Array(items) => Ok(items),
+            _ => Err(SaveError::InvalidField(field)),
+        }
+    }
+}
+
+/// JsonParser
+///
+/// Purpose: A small recursive-descent JSON parser over `text`.
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &'static str) -> SaveError {
+        SaveError::Json { offset: self.pos, message }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), SaveError> {
+        self.skip_whitespace();
+        if self.bump() == Some(c) { Ok(()) } else { Err(self.error(message)) }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, SaveError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => self.string().map(Json::String),
+            Some('0'..='9') => self.number(),
+            Some('-') => Err(self.error("negative numbers are not used")),
+            Some(_) => self.literal(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, SaveError> {
+        self.pos += 1;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a field name"));
+            }
+            let key = self.string()?;
+            self.expect(':', "expected `:`")?;
+            fields.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, SaveError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Json::Array(items)),
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SaveError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex = self.text.get(self.pos..self.pos + 4).ok_or_else(|| self.error("short \\u escape"))?;
+                            let code = u32::from_str_radix(hex, 16).map_err(|_| self.error("bad \\u escape"))?;
+                            self.pos += 4;
+                            char::from_u32(code).ok_or_else(|| self.error("unsupported \\u escape"))?
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    out.push(escaped);
+                }
+                Some(c) if c < ' ' => return Err(self.error("control character in string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, SaveError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('.' | 'e' | 'E')) {
+            return Err(self.error("only whole numbers are used"));
+        }
+        self.text[start..self.pos].parse().map(Json::Number).map_err(|_| self.error("number too large"))
+    }
+
+    fn literal(&mut self) -> Result<Json, SaveError> {
+        for (word, value) in [("null", Json::Null), ("true", Json::Bool(true)), ("false", Json::Bool(false))] {
+            if self.text[self.pos..].starts_with(word) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+        Err(self.error("unexpected character"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::LedgerError;
+    use crate::rng::Rng;
+    use crate::test_coins::coin;
+
+    /// One coin of every denomination, material, mint and grade.
+    fn every_variant() -> Vec<Coin> {
+        let mut coins = Vec::new();
+        for (i, &material) in Material::ALL.iter().enumerate() {
+            coins.push(Coin {
+                denom: Denomination::ALL[i % 5],
+                material,
+                diameter_mm: 20 + i as u16,
+                thickness_mm: 2,
+                year: 1000 + i as u16,
+                mint: Mint::ALL[i % 4],
+                condition: Condition { grade: Grade::ALL[i % 6], purity_pct: 100 - i as u8, uses: i as u32 * 1000 },
+            });
+        }
+        coins
+    }
+
+    fn v1_binary() -> Vec<u8> {
+        let mut bytes = b"HWC".to_vec();
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&[3, 5]); // gold, mithril
+        for n in [25u16, 2, 1020] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        bytes.push(2); // Mountain
+        bytes
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let coins = every_variant();
+        let bytes = to_binary(&coins).unwrap();
+        assert_eq!(bytes.len(), 9 + coins.len() * V2_RECORD);
+        assert_eq!(from_binary(&bytes), Ok(coins.clone()));
+        assert_eq!(from_json(&to_json(&coins)), Ok(coins));
+        assert_eq!(from_binary(&to_binary(&[]).unwrap()), Ok(vec![]));
+        assert_eq!(from_json(&to_json(&[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_tags_cover_every_variant() {
+        // Tags round-trip, and each `ALL` list holds exactly the tagged variants.
+        fn check<T: Copy + PartialEq + fmt::Debug>(all: &[T], to_tag: fn(T) -> u8, from_tag: fn(u8) -> Result<T, SaveError>) {
+            let decoded: Vec<T> = (0..=u8::MAX).filter_map(|tag| from_tag(tag).ok()).collect();
+            assert_eq!(decoded.len(), all.len(), "{all:?}");
+            for (tag, &value) in decoded.iter().enumerate() {
+                assert_eq!(usize::from(to_tag(value)), tag);
+                assert!(all.contains(&value), "{value:?} missing from ALL");
+            }
+        }
+        check(&Denomination::ALL, denomination_tag, denomination_from_tag);
+        check(&Material::ALL, material_tag, material_from_tag);
+        check(&Mint::ALL, mint_tag, mint_from_tag);
+        check(&Grade::ALL, grade_tag, grade_from_tag);
+        check(&[AccountKind::Player, AccountKind::Merchant, AccountKind::Bank, AccountKind::Mint], account_kind_tag, account_kind_from_tag);
+        check(&[Side::Debit, Side::Credit], side_tag, side_from_tag);
+    }
+
+    #[test]
+    fn test_names_cover_every_variant() {
+        fn check<T: Copy + PartialEq + fmt::Debug>(all: &[T], to_name: fn(T) -> &'static str, from_name: fn(&str) -> Result<T, SaveError>) {
+            for &value in all {
+                assert_eq!(from_name(to_name(value)), Ok(value));
+            }
+        }
+        check(&Denomination::ALL, denomination_name, denomination_from_name);
+        check(&Material::ALL, material_name, material_from_name);
+        check(&Mint::ALL, mint_name, mint_from_name);
+        check(&Grade::ALL, grade_name, grade_from_name);
+        check(&[AccountKind::Player, AccountKind::Merchant, AccountKind::Bank, AccountKind::Mint], account_kind_name, account_kind_from_name);
+        check(&[Side::Debit, Side::Credit], side_name, side_from_name);
+        assert_eq!(grade_from_name("veryfine"), Err(SaveError::UnknownName { kind: "grade", name: "veryfine".to_string() }));
+    }
+
+    #[test]
+    fn test_counts_that_do_not_fit_are_refused() {
+        let mut out = Vec::new();
+        assert_eq!(put_count(&mut out, u32::MAX as usize, "coins"), Ok(()));
+        assert_eq!(put_count(&mut out, u32::MAX as usize + 1, "coins"), Err(SaveError::TooLarge("coins")));
+        assert_eq!(out, u32::MAX.to_le_bytes());
+    }
+
+    /// Books with every account kind, a multi-entry posting, coins, a
+    /// reversal and names that need escaping in JSON.
+    fn sample_ledger() -> Ledger {
+        let mut ledger = Ledger::new();
+        let mint = ledger.open_account("Capital \"Old\" Mint", AccountKind::Mint);
+        let bank = ledger.open_account("Royal Bank", AccountKind::Bank);
+        let aria = ledger.open_account("Aria\nof the Vale", AccountKind::Player);
+        let borin = ledger.open_account("Borin", AccountKind::Merchant);
+        ledger.transfer_coins(1, "new issue", mint, bank, &every_variant()).unwrap();
+        ledger.transfer(2, "loan", bank, aria, Money::from_cp(500)).unwrap();
+        let cp = Money::from_cp;
+        ledger
+            .post(
+                3,
+                "sword, taxed",
+                vec![
+                    Entry { account: borin, side: Side::Debit, amount: cp(180) },
+                    Entry { account: bank, side: Side::Debit, amount: cp(20) },
+                    Entry { account: aria, side: Side::Credit, amount: cp(200) },
+                ],
+            )
+            .unwrap();
+        let overcharge = ledger.transfer(4, "overcharge \\ tab\t", aria, borin, cp(50)).unwrap();
+        ledger.reverse(overcharge, 5, "refund").unwrap();
+        ledger
+    }
+
+    #[test]
+    fn test_ledger_round_trips() {
+        let ledger = sample_ledger();
+        assert_eq!(ledger_from_binary(&ledger_to_binary(&ledger).unwrap()), Ok(ledger.clone()));
+        assert_eq!(ledger_from_json(&ledger_to_json(&ledger)), Ok(ledger.clone()));
+        let loaded = ledger_from_json(&ledger_to_json(&ledger)).unwrap();
+        assert_eq!(loaded.transactions()[4].reverses, Some(TxId(3)));
+        assert_eq!(loaded.account(AccountId(2)).map(|a| a.name.as_str()), Ok("Aria\nof the Vale"));
+
+        let empty = Ledger::new();
+        assert_eq!(ledger_from_binary(&ledger_to_binary(&empty).unwrap()), Ok(empty.clone()));
+        assert_eq!(ledger_from_json(&ledger_to_json(&empty)), Ok(empty));
+    }
+
+    #[test]
+    fn test_bad_ledger_is_an_error() {
+        let good = ledger_to_binary(&sample_ledger()).unwrap();
+        for len in 0..good.len() {
+            assert!(ledger_from_binary(&good[..len]).is_err(), "{len}");
+        }
+        let mut long = good.clone();
+        long.push(0);
+        assert_eq!(ledger_from_binary(&long), Err(SaveError::TrailingData));
+        assert_eq!(ledger_from_binary(&to_binary(&[]).unwrap()), Err(SaveError::BadMagic));
+        assert_eq!(ledger_from_binary(b"HWL\x01\x00"), Err(SaveError::UnsupportedVersion(1)));
+
+        // The books are checked as they load.
+        let json = ledger_to_json(&sample_ledger());
+        let unbalanced = json.replacen("\"amount\": 180", "\"amount\": 181", 1);
+        assert_eq!(
+            ledger_from_json(&unbalanced),
+            Err(SaveError::Ledger(LedgerError::Unbalanced { debits: Money::from_cp(201), credits: Money::from_cp(200) }))
+        );
+        let tx = |t: u64, debit: usize, credit: usize, reverses: &str| {
+            format!(
+                "{{\"timestamp\": {t}, \"memo\": \"\", \"entries\": [{{\"account\": {debit}, \"side\": \"Debit\", \"amount\": 5}}, \
+                 {{\"account\": {credit}, \"side\": \"Credit\", \"amount\": 5}}], \"coins\": [], \"reverses\": {reverses}}}"
+            )
+        };
+        let twice = format!(
+            "{{\"version\": 2, \"accounts\": [{{\"name\": \"A\", \"kind\": \"Player\"}}, {{\"name\": \"B\", \"kind\": \"Player\"}}], \
+             \"transactions\": [{}, {}, {}]}}",
+            tx(1, 1, 0, "null"),
+            tx(2, 0, 1, "0"),
+            tx(3, 0, 1, "0")
+        );
+        assert_eq!(ledger_from_json(&twice), Err(SaveError::Ledger(LedgerError::AlreadyReversed { tx: TxId(0), by: TxId(1) })));
+        let forward = json.replacen("\"reverses\": 3", "\"reverses\": 9", 1);
+        assert_eq!(ledger_from_json(&forward), Err(SaveError::Ledger(LedgerError::UnknownTransaction(TxId(9)))));
+        let stranger = json.replacen("\"account\": 3", "\"account\": 7", 1);
+        assert_eq!(ledger_from_json(&stranger), Err(SaveError::Ledger(LedgerError::UnknownAccount(AccountId(7)))));
+        assert_eq!(
+            ledger_from_json(&json.replacen("\"Merchant\"", "\"Guild\"", 1)),
+            Err(SaveError::UnknownName { kind: "account kind", name: "Guild".to_string() })
+        );
+        assert_eq!(ledger_from_json("{\"version\": 1, \"accounts\": []}"), Err(SaveError::UnsupportedVersion(1)));
+
+        let mut rng = Rng::new(52);
+        for _ in 0..2000 {
+            let mut bytes = good.clone();
+            let i = rng.index(bytes.len());
+            bytes[i] = rng.next_u64() as u8;
+            let _ = ledger_from_binary(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_wallet_round_trips() {
+        let wallet: Wallet = every_variant().into_iter().collect();
+        assert_eq!(wallet_from_binary(&wallet_to_binary(&wallet).unwrap()), Ok(wallet.clone()));
+        assert_eq!(wallet_from_json(&wallet_to_json(&wallet)), Ok(wallet));
+        assert_eq!(wallet_from_binary(b"HWC"), Err(SaveError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_version_1_migrates() {
+        let expected = Coin { year: 1020, ..coin(Denomination::Gold, Material::Mithril, Mint::Mountain) };
+        assert_eq!(from_binary(&v1_binary()), Ok(vec![expected]));
+        let json = r#"{"version": 1, "coins": [{"denom": "Gold", "material": "Mithril", "diameter_mm": 25,
+                       "thickness_mm": 2, "year": 1020, "mint": "Mountain", "note": "ignored"}]}"#;
+        assert_eq!(from_json(json), Ok(vec![expected]));
+    }
+
+    #[test]
+    fn test_bad_binary_is_an_error() {
+        let good = to_binary(&every_variant()).unwrap();
+        // Every truncation fails cleanly.
+        for len in 0..good.len() {
+            assert!(from_binary(&good[..len]).is_err(), "{len}");
+        }
+        let mut long = good.clone();
+        long.push(0);
+        assert_eq!(from_binary(&long), Err(SaveError::TrailingData));
+
+        assert_eq!(from_binary(b"XYZ\x02\x00\x00\x00\x00\x00"), Err(SaveError::BadMagic));
+        assert_eq!(from_binary(b"HWC\x09\x00\x00\x00\x00\x00"), Err(SaveError::UnsupportedVersion(9)));
+        assert_eq!(from_binary(b"HWC\x02\x00\xff\xff\xff\xff"), Err(SaveError::UnexpectedEnd));
+
+        let mut bad_material = v1_binary();
+        bad_material[10] = 42;
+        assert_eq!(from_binary(&bad_material), Err(SaveError::UnknownTag { kind: "material", tag: 42 }));
+
+        let mut bad_purity = to_binary(&every_variant()[..1]).unwrap();
+        bad_purity[9 + 10] = 101;
+        assert_eq!(from_binary(&bad_purity), Err(SaveError::InvalidField("purity_pct")));
+
+        // Random noise and random corruption never panic.
+        let mut rng = Rng::new(50);
+        for _ in 0..2000 {
+            let mut bytes = good.clone();
+            let i = rng.index(bytes.len());
+            bytes[i] = rng.next_u64() as u8;
+            let _ = from_binary(&bytes);
+            let noise: Vec<u8> = (0..rng.index(40)).map(|_| rng.next_u64() as u8).collect();
+            let _ = from_binary(&noise);
+        }
+    }
+
+    #[test]
+    fn test_bad_json_is_an_error() {
+        let good = to_json(&every_variant());
+        for (end, _) in good.trim_end().char_indices() {
+            assert!(from_json(&good[..end]).is_err(), "{end}");
+        }
+        assert!(matches!(from_json("{\"version\": 2, \"coins\": []} x"), Err(SaveError::Json { .. })));
+        assert!(matches!(from_json(&"[".repeat(10_000)), Err(SaveError::Json { message: "nested too deeply", .. })));
+        assert_eq!(from_json("{\"version\": 3, \"coins\": []}"), Err(SaveError::UnsupportedVersion(3)));
+        assert_eq!(from_json("{\"coins\": []}"), Err(SaveError::MissingField("version")));
+        assert_eq!(from_json("{\"version\": 2, \"coins\": {}}"), Err(SaveError::InvalidField("coins")));
+        assert_eq!(from_json("{\"version\": 99999999999999999999}"), Err(SaveError::Json { offset: 32, message: "number too large" }));
+
+        let unknown = good.replacen("\"Obsidian\"", "\"Adamantine\"", 1);
+        assert_eq!(from_json(&unknown), Err(SaveError::UnknownName { kind: "material", name: "Adamantine".to_string() }));
+        let huge = good.replacen("\"year\": 1000", "\"year\": 70000", 1);
+        assert_eq!(from_json(&huge), Err(SaveError::InvalidField("year")));
+
+        let mut rng = Rng::new(51);
+        let chars: Vec<char> = good.chars().collect();
+        for _ in 0..2000 {
+            let mut mangled = chars.clone();
+            let i = rng.index(mangled.len());
+            mangled[i] = ['{', '}', '[', ']', '"', ',', ':', '\\', '-', '9', 'x', 'é'][rng.index(12)];
+            let _ = from_json(&mangled.iter().collect::<String>());
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! spec — mint standards and counterfeit detection
+//!
+//! Each `Mint` publishes a `CoinSpec` for every denomination it strikes over
+//! a range of years: the metal, the diameter and the thickness, with
+//! tolerances. A `SpecBook` checks a `Coin` against the spec that covers its
+//! mint, denomination and year, and lists every way the coin differs.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::condition::Condition;
+use crate::{Coin, Denomination, Material, Mint};
+
+/// CoinSpec
+///
+/// Purpose: How one mint strikes one denomination during a range of years.
+/// Type: `struct CoinSpec`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSpec {
+    pub mint: Mint,
+    pub denom: Denomination,
+    pub years: RangeInclusive<u16>,
+    pub material: Material,
+    pub diameter_mm: u16,
+    pub diameter_tolerance_mm: u16,
+    pub thickness_mm: u16,
+    pub thickness_tolerance_mm: u16,
+}
+
+impl CoinSpec {
+    /// strike
+    ///
+    /// Purpose: A new, genuine coin made to this spec in `year`.
+    /// Type: `fn strike(&self, year: u16) -> Coin`
+    pub fn strike(&self, year: u16) -> Coin {
+        Coin {
+            denom: self.denom,
+            material: self.material,
+            diameter_mm: self.diameter_mm,
+            thickness_mm: self.thickness_mm,
+            year,
+            mint: self.mint,
+            condition: Condition::MINT,
+        }
+    }
+}
+
+/// Violation
+///
+/// Purpose: One way a coin fails to match its mint's published standard.
+/// Type: `enum Violation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// The mint never struck this denomination in this year.
+    NoSpecification { mint: Mint, denom: Denomination, year: u16 },
+    /// Struck in the wrong metal.
+    WrongMaterial { expected: Material, found: Material },
+    /// Diameter is outside `expected ± tolerance`.
+    WrongDiameter { expected: u16, tolerance: u16, found: u16 },
+    /// Thickness is outside `expected ± tolerance`.
+    WrongThickness { expected: u16, tolerance: u16, found: u16 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::NoSpecification { mint, denom, year } => {
+                write!(f, "{mint:?} mint issued no {denom:?} coins in year {year}")
+            }
+            Violation::WrongMaterial { expected, found } => {
+                write!(f, "struck in {found:?}, should be {expected:?}")
+            }
+            Violation::WrongDiameter { expected, tolerance, found } => {
+                write!(f, "diameter {found} mm, should be {expected}±{tolerance} mm")
+            }
+            Violation::WrongThickness { expected, tolerance, found } => {
+                write!(f, "thickness {found} mm, should be {expected}±{tolerance} mm")
+            }
+        }
+    }
+}
+
+/// SpecBook
+///
+/// Purpose: All published coin specifications, used to validate coins.
+/// Type: `struct SpecBook`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecBook {
+    specs: Vec<CoinSpec>,
+}
+
+/// Standard metal and diameter per denomination; every coin is 2 mm thick.
+const STANDARD_SIZES: [(Denomination, Material, u16); 5] = [
+    (Denomination::Copper, Material::Copper, 21),
+    (Denomination::Silver, Material::Silver, 22),
+    (Denomination::Electrum, Material::Electrum, 24),
+    (Denomination::Gold, Material::Gold, 25),
+    (Denomination::Platinum, Material::Platinum, 27),
+];
+
+impl SpecBook {
+    /// standard
+    ///
+    /// Purpose: The specs published by the four mints of the realm:
+    /// - Capital strikes everything, years 900–1100.
+    /// - Coastal strikes up to gold from 950; its silver was a broad 23 mm
+    ///   piece until 999.
+    /// - Mountain strikes everything from 1000.
+    /// - Desert strikes up to gold from 980.
+    ///
+    /// Type: `fn standard() -> SpecBook`
+    pub fn standard() -> SpecBook {
+        let issues: [(Mint, RangeInclusive<u16>, &[Denomination]); 4] = [
+            (Mint::Capital, 900..=1100, &Denomination::ALL),
+            (Mint::Coastal, 950..=1100, &Denomination::ALL[..4]),
+            (Mint::Mountain, 1000..=1100, &Denomination::ALL),
+            (Mint::Desert, 980..=1100, &Denomination::ALL[..4]),
+        ];
+        let mut specs = Vec::new();
+        for (mint, years, denoms) in issues {
+            for &(denom, material, diameter_mm) in STANDARD_SIZES.iter().filter(|s| denoms.contains(&s.0)) {
+                let spec = CoinSpec {
+                    mint,
+                    denom,
+                    years: years.clone(),
+                    material,
+                    diameter_mm,
+                    diameter_tolerance_mm: 1,
+                    thickness_mm: 2,
+                    thickness_tolerance_mm: 0,
+                };
+                if (mint, denom) == (Mint::Coastal, Denomination::Silver) {
+                    specs.push(CoinSpec { years: *years.start()..=999, diameter_mm: 23, ..spec.clone() });
+                    specs.push(CoinSpec { years: 1000..=*years.end(), ..spec });
+                } else {
+                    specs.push(spec);
+                }
+            }
+        }
+        SpecBook { specs }
+    }
+
+    /// specs
+    ///
+    /// Purpose: Every published spec.
+    /// Type: `fn specs(&self) -> &[CoinSpec]`
+    pub fn specs(&self) -> &[CoinSpec] {
+        &self.specs
+    }
+
+    /// spec_for
+    ///
+    /// Purpose: The spec covering a coin's mint, denomination and year.
+    /// Type: `fn spec_for(&self, coin: &Coin) -> Option<&CoinSpec>`
+    pub fn spec_for(&self, coin: &Coin) -> Option<&CoinSpec> {
+        self.find(coin.mint, coin.denom, coin.year)
+    }
+
+    /// find
+    ///
+    /// Purpose: The spec a mint uses for a denomination in a given year.
+    /// Type: `fn find(&self, mint: Mint, denom: Denomination, year: u16) -> Option<&CoinSpec>`
+    pub fn find(&self, mint: Mint, denom: Denomination, year: u16) -> Option<&CoinSpec> {
+        self.specs
+            .iter()
+            .find(|s| s.mint == mint && s.denom == denom && s.years.contains(&year))
+    }
+
+    /// validate
+    ///
+    /// Purpose: Check a coin against its mint's standard.
+    /// Returns: every violation found; empty means the coin looks genuine.
+    /// Type: `fn validate(&self, coin: &Coin) -> Vec<Violation>`
+    pub fn validate(&self, coin: &Coin) -> Vec<Violation> {
+        let Some(spec) = self.spec_for(coin) else {
+            return vec![Violation::NoSpecification { mint: coin.mint, denom: coin.denom, year: coin.year }];
+        };
+        let mut violations = Vec::new();
+        if coin.material != spec.material {
+            violations.push(Violation::WrongMaterial { expected: spec.material, found: coin.material });
+        }
+        if coin.diameter_mm.abs_diff(spec.diameter_mm) > spec.diameter_tolerance_mm {
+            violations.push(Violation::WrongDiameter {
+                expected: spec.diameter_mm,
+                tolerance: spec.diameter_tolerance_mm,
+                found: coin.diameter_mm,
+            });
+        }
+        if coin.thickness_mm.abs_diff(spec.thickness_mm) > spec.thickness_tolerance_mm {
+            violations.push(Violation::WrongThickness {
+                expected: spec.thickness_mm,
+                tolerance: spec.thickness_tolerance_mm,
+                found: coin.thickness_mm,
+            });
+        }
+        violations
+    }
+
+    /// is_genuine
+    ///
+    /// Purpose: True if the coin has no violations.
+    /// Type: `fn is_genuine(&self, coin: &Coin) -> bool`
+    pub fn is_genuine(&self, coin: &Coin) -> bool {
+        self.validate(coin).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_coins;
+
+    fn coin(denom: Denomination, material: Material, diameter_mm: u16, year: u16, mint: Mint) -> Coin {
+        Coin { diameter_mm, year, ..test_coins::coin(denom, material, mint) }
+    }
+
+    #[test]
+    fn test_genuine_coins_pass() {
+        let book = SpecBook::standard();
+        assert!(book.is_genuine(&coin(Denomination::Gold, Material::Gold, 25, 1023, Mint::Capital)));
+        assert!(book.is_genuine(&coin(Denomination::Copper, Material::Copper, 20, 1022, Mint::Mountain)));
+        assert!(book.is_genuine(&coin(Denomination::Silver, Material::Silver, 23, 960, Mint::Coastal)));
+
+        let spec = book.find(Mint::Desert, Denomination::Electrum, 1021).unwrap();
+        assert!(book.is_genuine(&spec.strike(1021)));
+    }
+
+    #[test]
+    fn test_mithril_gold_is_flagged() {
+        let book = SpecBook::standard();
+        let fake = coin(Denomination::Gold, Material::Mithril, 25, 1020, Mint::Mountain);
+        assert_eq!(
+            book.validate(&fake),
+            vec![Violation::WrongMaterial { expected: Material::Gold, found: Material::Mithril }]
+        );
+    }
+
+    #[test]
+    fn test_multiple_violations_and_missing_spec() {
+        let book = SpecBook::standard();
+        let mut bad = coin(Denomination::Silver, Material::Copper, 25, 1010, Mint::Coastal);
+        bad.thickness_mm = 3;
+        assert_eq!(
+            book.validate(&bad),
+            vec![
+                Violation::WrongMaterial { expected: Material::Silver, found: Material::Copper },
+                Violation::WrongDiameter { expected: 22, tolerance: 1, found: 25 },
+                Violation::WrongThickness { expected: 2, tolerance: 0, found: 3 },
+            ]
+        );
+
+        let desert_platinum = coin(Denomination::Platinum, Material::Platinum, 27, 1024, Mint::Desert);
+        let too_early = coin(Denomination::Gold, Material::Gold, 25, 990, Mint::Mountain);
+        for c in [desert_platinum, too_early] {
+            assert_eq!(
+                book.validate(&c),
+                vec![Violation::NoSpecification { mint: c.mint, denom: c.denom, year: c.year }]
+            );
+        }
+    }
+}
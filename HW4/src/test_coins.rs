@@ -0,0 +1,16 @@
+//! test_coins — the coin factory shared by unit tests
+//!
+//! Notes:
+//! - Tests that care about size, year or condition override those fields
+//!   with struct update syntax: `Coin { year: 990, ..coin(...) }`.
+
+use crate::condition::Condition;
+use crate::{Coin, Denomination, Material, Mint};
+
+/// coin
+///
+/// Purpose: A 25×2 mm coin struck in 1023, in mint condition.
+/// Type: `fn coin(denom: Denomination, material: Material, mint: Mint) -> Coin`
+pub fn coin(denom: Denomination, material: Material, mint: Mint) -> Coin {
+    Coin { denom, material, diameter_mm: 25, thickness_mm: 2, year: 1023, mint, condition: Condition::MINT }
+}
@@ -0,0 +1,256 @@
+//! wallet — a purse of actual coins, grouped by kind
+//!
+//! Coins are kept in groups keyed by `CoinKind` (denomination, material and
+//! mint). Each group keeps the individual `Coin`s, so year and size survive a
+//! deposit/withdraw round trip.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::change;
+use crate::money::{Money, MoneyError};
+use crate::{Coin, Denomination, Material, Mint};
+
+/// CoinKind
+///
+/// Purpose: The grouping key for coins in a wallet.
+/// Type: `struct CoinKind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CoinKind {
+    pub denom: Denomination,
+    pub material: Material,
+    pub mint: Mint,
+}
+
+impl CoinKind {
+    /// of
+    ///
+    /// Purpose: The kind a coin belongs to.
+    /// Type: `fn of(coin: &Coin) -> CoinKind`
+    pub fn of(coin: &Coin) -> CoinKind {
+        CoinKind { denom: coin.denom, material: coin.material, mint: coin.mint }
+    }
+}
+
+/// WalletError
+///
+/// Purpose: Why a withdrawal from a wallet failed. The wallet is left
+/// unchanged whenever one of these is returned.
+/// Type: `enum WalletError`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WalletError {
+    /// The requested coin is not in the wallet.
+    CoinNotFound(Coin),
+    /// The wallet holds less than `requested` in total.
+    InsufficientFunds { requested: Money, available: Money },
+    /// The wallet holds enough, but no set of its coins adds up exactly.
+    NoExactAmount { requested: Money },
+    /// Adding up the wallet's contents overflowed.
+    Money(MoneyError),
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletError::CoinNotFound(coin) => write!(f, "coin not in wallet: {coin}"),
+            WalletError::InsufficientFunds { requested, available } => {
+                write!(f, "insufficient funds: requested {requested}, wallet holds {available}")
+            }
+            WalletError::NoExactAmount { requested } => {
+                write!(f, "wallet coins cannot make exactly {requested}")
+            }
+            WalletError::Money(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for WalletError {}
+
+impl From<MoneyError> for WalletError {
+    fn from(e: MoneyError) -> Self {
+        WalletError::Money(e)
+    }
+}
+
+/// Wallet
+///
+/// Purpose: Holds coins grouped by `CoinKind`; supports deposits and
+/// withdrawals of specific coins or of an amount of `Money`.
+/// Type: `struct Wallet`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Wallet {
+    groups: BTreeMap<CoinKind, Vec<Coin>>,
+}
+
+impl Wallet {
+    /// new
+    ///
+    /// Purpose: An empty wallet.
+    /// Type: `fn new() -> Wallet`
+    pub fn new() -> Wallet {
+        Wallet::default()
+    }
+
+    /// deposit
+    ///
+    /// Purpose: Put one coin into the wallet.
+    /// Type: `fn deposit(&mut self, coin: Coin)`
+    pub fn deposit(&mut self, coin: Coin) {
+        self.groups.entry(CoinKind::of(&coin)).or_default().push(coin);
+    }
+
+    /// withdraw_coin
+    ///
+    /// Purpose: Take out one coin equal to `coin`.
+    /// Returns: the removed coin, or `WalletError::CoinNotFound`.
+    /// Type: `fn withdraw_coin(&mut self, coin: &Coin) -> Result<Coin, WalletError>`
+    pub fn withdraw_coin(&mut self, coin: &Coin) -> Result<Coin, WalletError> {
+        let kind = CoinKind::of(coin);
+        let group = self.groups.get_mut(&kind).ok_or(WalletError::CoinNotFound(*coin))?;
+        let pos = group.iter().position(|c| c == coin).ok_or(WalletError::CoinNotFound(*coin))?;
+        let taken = group.swap_remove(pos);
+        if group.is_empty() {
+            self.groups.remove(&kind);
+        }
+        Ok(taken)
+    }
+
+    /// withdraw_amount
+    ///
+    /// Purpose: Take out the fewest coins whose face value adds up to exactly
+    /// `amount` (see `change::make_change`).
+    /// Returns: the removed coins, or `InsufficientFunds` / `NoExactAmount`.
+    /// Type: `fn withdraw_amount(&mut self, amount: Money) -> Result<Vec<Coin>, WalletError>`
+    pub fn withdraw_amount(&mut self, amount: Money) -> Result<Vec<Coin>, WalletError> {
+        // Plan first so a failed withdrawal leaves the wallet untouched.
+        let picked = change::make_change(self, amount)?;
+        self.withdraw_all(&picked)?;
+        Ok(picked)
+    }
+
+    /// withdraw_all
+    ///
+    /// Purpose: Take out every coin in `coins`. Either all are removed or,
+    /// if any is missing, none are.
+    /// Type: `fn withdraw_all(&mut self, coins: &[Coin]) -> Result<(), WalletError>`
+    pub fn withdraw_all(&mut self, coins: &[Coin]) -> Result<(), WalletError> {
+        let before = self.clone();
+        for coin in coins {
+            if let Err(e) = self.withdraw_coin(coin) {
+                *self = before;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// value_in_cp
+    ///
+    /// Purpose: Total face value of everything in the wallet.
+    /// Type: `fn value_in_cp(&self) -> Result<Money, MoneyError>`
+    pub fn value_in_cp(&self) -> Result<Money, MoneyError> {
+        self.coins().map(Coin::value_in_cp).sum()
+    }
+
+    /// worth
+    ///
+    /// Purpose: What everything in the wallet is worth (`Coin::worth`).
+    /// Type: `fn worth(&self) -> Result<Money, MoneyError>`
+    pub fn worth(&self) -> Result<Money, MoneyError> {
+        self.coins().sum()
+    }
+
+    /// coins
+    ///
+    /// Purpose: Iterate over every coin, group by group.
+    /// Type: `fn coins(&self) -> impl Iterator<Item = &Coin>`
+    pub fn coins(&self) -> impl Iterator<Item = &Coin> {
+        self.groups.values().flatten()
+    }
+
+    /// groups
+    ///
+    /// Purpose: Iterate over each kind held and how many coins of it.
+    /// Type: `fn groups(&self) -> impl Iterator<Item = (CoinKind, usize)>`
+    pub fn groups(&self) -> impl Iterator<Item = (CoinKind, usize)> + '_ {
+        self.groups.iter().map(|(kind, coins)| (*kind, coins.len()))
+    }
+
+    /// len
+    ///
+    /// Purpose: Number of coins in the wallet.
+    /// Type: `fn len(&self) -> usize`
+    pub fn len(&self) -> usize {
+        self.groups.values().map(Vec::len).sum()
+    }
+
+    /// is_empty
+    ///
+    /// Purpose: True if the wallet holds no coins.
+    /// Type: `fn is_empty(&self) -> bool`
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+impl FromIterator<Coin> for Wallet {
+    fn from_iter<I: IntoIterator<Item = Coin>>(iter: I) -> Self {
+        let mut wallet = Wallet::new();
+        for coin in iter {
+            wallet.deposit(coin);
+        }
+        wallet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_coins::coin;
+
+    #[test]
+    fn test_deposit_groups_and_totals() {
+        let gold = coin(Denomination::Gold, Material::Gold, Mint::Capital);
+        let fake = coin(Denomination::Gold, Material::Mithril, Mint::Mountain);
+        let wallet: Wallet = [gold, gold, fake].into_iter().collect();
+        assert_eq!(wallet.len(), 3);
+        assert_eq!(wallet.groups().count(), 2);
+        assert_eq!(wallet.value_in_cp(), Ok(Money::from_cp(300)));
+    }
+
+    #[test]
+    fn test_withdraw_coin() {
+        let silver = coin(Denomination::Silver, Material::Silver, Mint::Coastal);
+        let copper = coin(Denomination::Copper, Material::Copper, Mint::Desert);
+        let mut wallet: Wallet = [silver].into_iter().collect();
+        assert_eq!(wallet.withdraw_coin(&copper), Err(WalletError::CoinNotFound(copper)));
+        assert_eq!(wallet.withdraw_coin(&silver), Ok(silver));
+        assert!(wallet.is_empty());
+    }
+
+    #[test]
+    fn test_withdraw_amount() {
+        let gold = coin(Denomination::Gold, Material::Gold, Mint::Capital);
+        let electrum = coin(Denomination::Electrum, Material::Electrum, Mint::Desert);
+        let silver = coin(Denomination::Silver, Material::Silver, Mint::Coastal);
+        let mut wallet: Wallet = [gold, electrum, silver, silver].into_iter().collect();
+
+        let taken = wallet.withdraw_amount(Money::from_cp(70)).unwrap();
+        assert_eq!(taken, vec![electrum, silver, silver]);
+        assert_eq!(wallet.value_in_cp(), Ok(Money::from_cp(100)));
+
+        assert_eq!(
+            wallet.withdraw_amount(Money::from_cp(500)),
+            Err(WalletError::InsufficientFunds {
+                requested: Money::from_cp(500),
+                available: Money::from_cp(100),
+            })
+        );
+        assert_eq!(
+            wallet.withdraw_amount(Money::from_cp(5)),
+            Err(WalletError::NoExactAmount { requested: Money::from_cp(5) })
+        );
+        assert_eq!(wallet.len(), 1);
+    }
+}
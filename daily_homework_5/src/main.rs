@@ -3,8 +3,11 @@
 //! This module provides simple text and file helpers that will later be useful
 //! in the virtual-machine project.
 
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
 
 /// read_program_file
 ///
@@ -59,6 +62,120 @@ pub fn split_string(input: &str) -> Vec<String> {
         .collect()
 }
 
+/// Word
+///
+/// Purpose: One word produced by `split_quoted`, with quotes and escapes
+/// already removed.
+///
+/// Fields:
+/// - `text: String` — the unquoted, unescaped word.
+/// - `span: Range<usize>` — byte range of the raw word in the input,
+///   including any quote characters.
+///
+/// Type: `struct Word`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub text: String,
+    pub span: Range<usize>,
+}
+
+/// SplitError
+///
+/// Purpose: Reasons `split_quoted` can reject its input. Each variant carries
+/// the byte offset where the problem starts.
+///
+/// Type: `enum SplitError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitError {
+    /// A `'` or `"` was opened at `start` and never closed.
+    UnterminatedQuote { quote: char, start: usize },
+    /// The input ended right after a `\` at `start`.
+    TrailingBackslash { start: usize },
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitError::UnterminatedQuote { quote, start } => {
+                write!(f, "unterminated {quote} quote starting at byte {start}")
+            }
+            SplitError::TrailingBackslash { start } => {
+                write!(f, "trailing backslash at byte {start}")
+            }
+        }
+    }
+}
+
+impl Error for SplitError {}
+
+/// split_quoted
+///
+/// Purpose: Split a string into words like a command line. Whitespace
+/// separates words, except inside quotes:
+/// - `'...'` keeps everything literally (no escapes).
+/// - `"..."` keeps whitespace; `\` escapes the next character.
+/// - Outside quotes, `\` escapes the next character (e.g. `a\ b` is one word).
+///
+/// Quoted and unquoted parts next to each other join into one word, so
+/// `say"hi there"` gives `sayhi there`. An empty pair of quotes gives an
+/// empty word.
+///
+/// Parameters:
+/// - `input: &str`
+///
+/// Returns:
+/// - `Result<Vec<Word>, SplitError>` — the words with their byte spans, or
+///   the first unterminated quote / trailing backslash.
+///
+/// Type: `fn split_quoted(input: &str) -> Result<Vec<Word>, SplitError>`
+pub fn split_quoted(input: &str) -> Result<Vec<Word>, SplitError> {
+    let mut words = Vec::new();
+    let mut chars = input.char_indices();
+    // the word being built and where it started, if we are inside one
+    let mut current: Option<(String, usize)> = None;
+
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            if let Some((text, start)) = current.take() {
+                words.push(Word { text, span: start..i });
+            }
+            continue;
+        }
+
+        let text = &mut current.get_or_insert_with(|| (String::new(), i)).0;
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some((_, '\'')) => break,
+                    Some((_, ch)) => text.push(ch),
+                    None => return Err(SplitError::UnterminatedQuote { quote: '\'', start: i }),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((j, '\\')) => match chars.next() {
+                        Some((_, ch)) => text.push(ch),
+                        None => return Err(SplitError::TrailingBackslash { start: j }),
+                    },
+                    Some((_, ch)) => text.push(ch),
+                    None => return Err(SplitError::UnterminatedQuote { quote: '"', start: i }),
+                }
+            },
+            '\\' => match chars.next() {
+                Some((_, ch)) => text.push(ch),
+                None => return Err(SplitError::TrailingBackslash { start: i }),
+            },
+            _ => text.push(c),
+        }
+    }
+
+    if let Some((text, start)) = current {
+        words.push(Word { text, span: start..input.len() });
+    }
+    Ok(words)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,6 +204,38 @@ mod tests {
         assert_eq!(lines, vec!["line1", "line2", "line3"]);
         std::fs::remove_file(path).unwrap();
     }
+
+    #[test]
+    fn test_split_quoted_quotes_and_spans() {
+        let input = r#"say "hello world" 'it\s' a\ b"#;
+        let words = split_quoted(input).unwrap();
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["say", "hello world", "it\\s", "a b"]);
+        assert_eq!(words[0].span, 0..3);
+        assert_eq!(&input[words[1].span.clone()], "\"hello world\"");
+        assert_eq!(&input[words[3].span.clone()], "a\\ b");
+    }
+
+    #[test]
+    fn test_split_quoted_escapes_and_joins() {
+        let words = split_quoted(r#"x"a \"b\" c"y '' "#).unwrap();
+        assert_eq!(words[0].text, r#"xa "b" cy"#);
+        assert_eq!(words[1], Word { text: String::new(), span: 14..16 });
+        assert_eq!(words.len(), 2);
+    }
+
+    #[test]
+    fn test_split_quoted_errors() {
+        assert_eq!(
+            split_quoted("say \"hello"),
+            Err(SplitError::UnterminatedQuote { quote: '"', start: 4 })
+        );
+        assert_eq!(
+            split_quoted("a 'b"),
+            Err(SplitError::UnterminatedQuote { quote: '\'', start: 2 })
+        );
+        assert_eq!(split_quoted("end\\"), Err(SplitError::TrailingBackslash { start: 3 }));
+    }
 }
 
 fn main() {
@@ -96,6 +245,11 @@ fn main() {
     println!("Is 'print' a keyword? {}", crate::is_keyword("print"));
     println!("Is 'dog' a keyword? {}", crate::is_keyword("dog"));
 
+    match crate::split_quoted(r#"say "hello world" 'to you'"#) {
+        Ok(words) => println!("Quoted words: {words:?}"),
+        Err(e) => eprintln!("Error: {e}"),
+    }
+
     match crate::read_program_file("Cargo.toml") {
         Ok(lines) => println!("Read {} lines from Cargo.toml", lines.len()),
         Err(e) => eprintln!("Error: {e}"),